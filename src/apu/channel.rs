@@ -0,0 +1,84 @@
+/// Length counter which silences the channel when it reaches zero
+#[derive(Debug)]
+pub struct Length {
+    max: u16,
+    counter: u16,
+    pub enabled: bool,
+}
+impl Length {
+    pub fn new(max: u16) -> Length {
+        Length {
+            max,
+            counter: 0,
+            enabled: false,
+        }
+    }
+
+    /// Loads the counter from the length bits of NRx1
+    pub fn load(&mut self, value: u8) {
+        self.counter = self.max - (value as u16 & (self.max - 1));
+    }
+
+    pub fn trigger(&mut self) {
+        if self.counter == 0 {
+            self.counter = self.max;
+        }
+    }
+
+    /// Returns true if the counter expired and the channel is disabled
+    pub fn clock(&mut self) -> bool {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            return self.counter == 0;
+        }
+        false
+    }
+}
+
+/// Volume envelope controlled by NRx2
+#[derive(Debug, Default)]
+pub struct Envelope {
+    register: u8,
+    pub volume: u8,
+    timer: u8,
+}
+impl Envelope {
+    #[inline]
+    fn period(&self) -> u8 {
+        self.register & 0x07
+    }
+
+    #[inline]
+    fn increase(&self) -> bool {
+        self.register & 0x08 != 0
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+    }
+
+    /// The upper 5 bits of NRx2 power the DAC of the channel
+    pub fn dac_enabled(&self) -> bool {
+        self.register & 0xF8 != 0
+    }
+
+    pub fn trigger(&mut self) {
+        self.volume = self.register >> 4;
+        self.timer = self.period();
+    }
+
+    pub fn clock(&mut self) {
+        if self.period() == 0 {
+            return;
+        }
+        self.timer = self.timer.saturating_sub(1);
+        if self.timer == 0 {
+            self.timer = self.period();
+            if self.increase() && self.volume < 0x0F {
+                self.volume += 1;
+            } else if !self.increase() && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+}
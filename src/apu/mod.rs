@@ -0,0 +1,240 @@
+mod channel;
+mod noise;
+mod square;
+#[cfg(test)]
+mod tests;
+mod wave;
+
+use self::noise::Noise;
+use self::square::Square;
+use self::wave::Wave;
+use gameboy::CLOCK_SPEED;
+use std::mem;
+
+pub const NR10_REGISTER: u16 = 0xFF10;
+pub const NR50_REGISTER: u16 = 0xFF24;
+pub const NR51_REGISTER: u16 = 0xFF25;
+pub const NR52_REGISTER: u16 = 0xFF26;
+pub const WAVE_RAM_START: u16 = 0xFF30;
+pub const WAVE_RAM_END: u16 = 0xFF3F;
+
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+/// The frame sequencer runs at 512 Hz
+const FRAME_SEQUENCER_PERIOD: u32 = (CLOCK_SPEED / 512) as u32;
+
+/// Bits that read as 1 in 0xFF10-0xFF2F
+const READ_MASKS: [u8; 0x20] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // NR20-NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // NR40-NR44
+    0x00, 0x00, 0x70, // NR50-NR52
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, // Unused
+];
+
+trait Channel {
+    /// Writes NRx0-NRx4, `register` is 0-4
+    fn write(&mut self, register: u8, value: u8);
+    /// Advances the frequency timer by `cycles` clock cycles
+    fn step(&mut self, cycles: u32);
+    fn clock_length(&mut self);
+    fn clock_envelope(&mut self);
+    fn enabled(&self) -> bool;
+    /// Digital output of the channel, 0-15
+    fn output(&self) -> u8;
+}
+
+/// Audio processing unit.
+///
+/// Produces interleaved stereo samples in the range 0.0-1.0 at the
+/// sample rate given to `new`. The samples are collected with `take_samples`.
+pub struct Apu {
+    powered: bool,
+    registers: [u8; 0x20],
+    square1: Square,
+    square2: Square,
+    wave: Wave,
+    noise: Noise,
+    frame_step: u8,
+    frame_timer: u32,
+    sample_rate: u32,
+    /// Accumulates `sample_rate` per cycle, a sample is taken every
+    /// time it reaches `CLOCK_SPEED`
+    sample_timer: u64,
+    samples: Vec<f32>,
+}
+
+impl Apu {
+    pub fn new(sample_rate: u32) -> Apu {
+        Apu {
+            powered: false,
+            registers: [0; 0x20],
+            square1: Square::new(true),
+            square2: Square::new(false),
+            wave: Wave::new([0; 16]),
+            noise: Noise::new(),
+            frame_step: 0,
+            frame_timer: FRAME_SEQUENCER_PERIOD,
+            sample_rate,
+            sample_timer: 0,
+            samples: vec![],
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.sample_timer = 0;
+    }
+
+    /// Interleaved left and right samples produced since the previous call
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        mem::take(&mut self.samples)
+    }
+
+    fn channels(&self) -> [&dyn Channel; 4] {
+        [&self.square1, &self.square2, &self.wave, &self.noise]
+    }
+
+    fn clock_frame_sequencer(&mut self) {
+        if self.frame_step & 0x01 == 0 {
+            self.square1.clock_length();
+            self.square2.clock_length();
+            self.wave.clock_length();
+            self.noise.clock_length();
+        }
+        if self.frame_step == 2 || self.frame_step == 6 {
+            self.square1.clock_sweep();
+        }
+        if self.frame_step == 7 {
+            self.square1.clock_envelope();
+            self.square2.clock_envelope();
+            self.noise.clock_envelope();
+        }
+        self.frame_step = (self.frame_step + 1) & 0x07;
+    }
+
+    /// Mixes the channels according to NR50 and NR51
+    fn mix(&self) -> (f32, f32) {
+        let panning = self.registers[(NR51_REGISTER - NR10_REGISTER) as usize];
+        let volume = self.registers[(NR50_REGISTER - NR10_REGISTER) as usize];
+        let (mut left, mut right) = (0.0, 0.0);
+        for (i, channel) in self.channels().iter().enumerate() {
+            let output = channel.output() as f32 / 15.0;
+            if panning & (0x10 << i) != 0 {
+                left += output;
+            }
+            if panning & (0x01 << i) != 0 {
+                right += output;
+            }
+        }
+        let left_volume = ((volume >> 4) & 0x07) as f32 + 1.0;
+        let right_volume = (volume & 0x07) as f32 + 1.0;
+        (
+            left / 4.0 * left_volume / 8.0,
+            right / 4.0 * right_volume / 8.0,
+        )
+    }
+
+    fn push_sample(&mut self) {
+        // Nobody is consuming the samples, drop them
+        // instead of buffering more than a second
+        if self.samples.len() >= 2 * self.sample_rate as usize {
+            return;
+        }
+        let (left, right) = self.mix();
+        self.samples.push(left);
+        self.samples.push(right);
+    }
+
+    /// Advances the APU by `cycles` clock cycles
+    pub fn step(&mut self, cycles: usize) {
+        let clock_speed = CLOCK_SPEED as u64;
+        let sample_rate = self.sample_rate.max(1) as u64;
+        let mut cycles = cycles as u32;
+        while cycles > 0 {
+            let until_sample = (clock_speed - self.sample_timer).div_ceil(sample_rate) as u32;
+            let chunk = cycles.min(until_sample).min(self.frame_timer);
+            cycles -= chunk;
+
+            if self.powered {
+                self.square1.step(chunk);
+                self.square2.step(chunk);
+                self.wave.step(chunk);
+                self.noise.step(chunk);
+                self.frame_timer -= chunk;
+                if self.frame_timer == 0 {
+                    self.frame_timer = FRAME_SEQUENCER_PERIOD;
+                    self.clock_frame_sequencer();
+                }
+            }
+
+            self.sample_timer += chunk as u64 * sample_rate;
+            if self.sample_timer >= clock_speed {
+                self.sample_timer -= clock_speed;
+                self.push_sample();
+            }
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            NR52_REGISTER => {
+                let status = self
+                    .channels()
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, channel)| channel.enabled())
+                    .fold(0, |status, (i, _)| status | 1 << i);
+                (self.powered as u8) << 7 | 0x70 | status
+            }
+            WAVE_RAM_START..=WAVE_RAM_END => self.wave.ram[(addr - WAVE_RAM_START) as usize],
+            _ => {
+                let index = (addr - NR10_REGISTER) as usize;
+                self.registers[index] | READ_MASKS[index]
+            }
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            NR52_REGISTER => self.write_power(value & 0x80 != 0),
+            WAVE_RAM_START..=WAVE_RAM_END => {
+                self.wave.ram[(addr - WAVE_RAM_START) as usize] = value;
+            }
+            // Registers can't be written while the APU is off
+            _ if !self.powered => {}
+            _ => {
+                let index = addr - NR10_REGISTER;
+                self.registers[index as usize] = value;
+                let register = (index % 5) as u8;
+                match index {
+                    0x00..=0x04 => self.square1.write(register, value),
+                    0x05..=0x09 => self.square2.write(register, value),
+                    0x0A..=0x0E => self.wave.write(register, value),
+                    0x0F..=0x13 => self.noise.write(register, value),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn write_power(&mut self, on: bool) {
+        if on && !self.powered {
+            self.frame_step = 0;
+            self.frame_timer = FRAME_SEQUENCER_PERIOD;
+        } else if !on && self.powered {
+            // Powering off clears all registers except the wave RAM
+            self.registers = [0; 0x20];
+            self.square1 = Square::new(true);
+            self.square2 = Square::new(false);
+            self.wave = Wave::new(self.wave.ram);
+            self.noise = Noise::new();
+        }
+        self.powered = on;
+    }
+}
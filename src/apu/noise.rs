@@ -0,0 +1,113 @@
+use super::channel::{Envelope, Length};
+use super::Channel;
+
+const DIVISORS: [u32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+/// Channel 4, outputs the inverted low bit of a linear feedback shift register
+#[derive(Debug)]
+pub struct Noise {
+    enabled: bool,
+    length: Length,
+    envelope: Envelope,
+    /// NR43
+    register: u8,
+    lfsr: u16,
+    timer: u32,
+}
+impl Noise {
+    pub fn new() -> Noise {
+        Noise {
+            enabled: false,
+            length: Length::new(64),
+            envelope: Envelope::default(),
+            register: 0,
+            lfsr: 0x7FFF,
+            timer: 0,
+        }
+    }
+
+    #[inline]
+    fn clock_shift(&self) -> u8 {
+        self.register >> 4
+    }
+
+    #[inline]
+    fn period(&self) -> u32 {
+        DIVISORS[(self.register & 0x07) as usize] << self.clock_shift()
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.envelope.dac_enabled();
+        self.length.trigger();
+        self.envelope.trigger();
+        self.timer = self.period();
+        self.lfsr = 0x7FFF;
+    }
+
+    fn shift_lfsr(&mut self) {
+        let bit = (self.lfsr ^ (self.lfsr >> 1)) & 0x01;
+        self.lfsr = (self.lfsr >> 1) | (bit << 14);
+        // 7-bit mode also feeds the bit back to bit 6
+        if self.register & 0x08 != 0 {
+            self.lfsr = (self.lfsr & !0x40) | (bit << 6);
+        }
+    }
+}
+
+impl Channel for Noise {
+    fn write(&mut self, register: u8, value: u8) {
+        match register {
+            // NR40 does not exist
+            0 => {}
+            1 => self.length.load(value),
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.register = value,
+            4 => {
+                self.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+            _ => unreachable!("Noise channel register {}", register),
+        }
+    }
+
+    fn step(&mut self, mut cycles: u32) {
+        while cycles >= self.timer {
+            cycles -= self.timer;
+            self.timer = self.period();
+            // The LFSR is not clocked with clock shifts 14 and 15
+            if self.clock_shift() < 14 {
+                self.shift_lfsr();
+            }
+        }
+        self.timer -= cycles;
+    }
+
+    fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled && self.lfsr & 0x01 == 0 {
+            self.envelope.volume
+        } else {
+            0
+        }
+    }
+}
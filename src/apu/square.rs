@@ -0,0 +1,198 @@
+use super::channel::{Envelope, Length};
+use super::Channel;
+
+/// Waveforms of the 12.5%, 25%, 50% and 75% duty cycles
+const DUTY_PATTERNS: [u8; 4] = [0b0000_0001, 0b1000_0001, 0b1000_0111, 0b0111_1110];
+
+/// Frequency sweep of channel 1, controlled by NR10
+#[derive(Debug, Default)]
+struct Sweep {
+    register: u8,
+    enabled: bool,
+    timer: u8,
+    shadow: u16,
+    /// A frequency has been calculated in negate mode since the trigger
+    negated: bool,
+}
+impl Sweep {
+    #[inline]
+    fn period(&self) -> u8 {
+        (self.register >> 4) & 0x07
+    }
+
+    #[inline]
+    fn negate(&self) -> bool {
+        self.register & 0x08 != 0
+    }
+
+    #[inline]
+    fn shift(&self) -> u8 {
+        self.register & 0x07
+    }
+
+    fn reload_timer(&mut self) {
+        // Period 0 is treated as 8 by the timer
+        self.timer = match self.period() {
+            0 => 8,
+            period => period,
+        };
+    }
+
+    fn calculate(&mut self) -> u16 {
+        let delta = self.shadow >> self.shift();
+        if self.negate() {
+            self.negated = true;
+            self.shadow - delta
+        } else {
+            self.shadow + delta
+        }
+    }
+}
+
+/// Square wave channels 1 and 2. Only channel 1 has the sweep unit.
+#[derive(Debug)]
+pub struct Square {
+    sweep: Option<Sweep>,
+    enabled: bool,
+    length: Length,
+    envelope: Envelope,
+    duty: u8,
+    duty_step: u8,
+    frequency: u16,
+    timer: u32,
+}
+impl Square {
+    pub fn new(has_sweep: bool) -> Square {
+        Square {
+            sweep: if has_sweep {
+                Some(Sweep::default())
+            } else {
+                None
+            },
+            enabled: false,
+            length: Length::new(64),
+            envelope: Envelope::default(),
+            duty: 0,
+            duty_step: 0,
+            frequency: 0,
+            timer: 0,
+        }
+    }
+
+    #[inline]
+    fn period(&self) -> u32 {
+        (2048 - self.frequency as u32) * 4
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.envelope.dac_enabled();
+        self.length.trigger();
+        self.envelope.trigger();
+        self.timer = self.period();
+        let frequency = self.frequency;
+        if let Some(ref mut sweep) = self.sweep {
+            sweep.shadow = frequency;
+            sweep.negated = false;
+            sweep.reload_timer();
+            sweep.enabled = sweep.period() != 0 || sweep.shift() != 0;
+            // The overflow check is done immediately
+            if sweep.shift() != 0 && sweep.calculate() > 2047 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    pub fn clock_sweep(&mut self) {
+        let sweep = match self.sweep {
+            Some(ref mut sweep) => sweep,
+            None => return,
+        };
+        sweep.timer = sweep.timer.saturating_sub(1);
+        if sweep.timer > 0 {
+            return;
+        }
+        sweep.reload_timer();
+        if !sweep.enabled || sweep.period() == 0 {
+            return;
+        }
+        let frequency = sweep.calculate();
+        if frequency > 2047 {
+            self.enabled = false;
+        } else if sweep.shift() != 0 {
+            sweep.shadow = frequency;
+            self.frequency = frequency;
+            // The new frequency is checked for overflow again
+            if sweep.calculate() > 2047 {
+                self.enabled = false;
+            }
+        }
+    }
+}
+
+impl Channel for Square {
+    fn write(&mut self, register: u8, value: u8) {
+        match register {
+            0 => {
+                if let Some(ref mut sweep) = self.sweep {
+                    // Leaving negate mode after a negated calculation
+                    // disables the channel
+                    if sweep.negated && value & 0x08 == 0 {
+                        self.enabled = false;
+                    }
+                    sweep.register = value;
+                }
+            }
+            1 => {
+                self.duty = value >> 6;
+                self.length.load(value);
+            }
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.frequency = (self.frequency & 0x700) | value as u16,
+            4 => {
+                self.frequency = (self.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
+                self.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+            _ => unreachable!("Square channel register {}", register),
+        }
+    }
+
+    fn step(&mut self, mut cycles: u32) {
+        while cycles >= self.timer {
+            cycles -= self.timer;
+            self.timer = self.period();
+            self.duty_step = (self.duty_step + 1) & 0x07;
+        }
+        self.timer -= cycles;
+    }
+
+    fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn output(&self) -> u8 {
+        let high = DUTY_PATTERNS[self.duty as usize] >> (7 - self.duty_step) & 0x01 != 0;
+        if self.enabled && high {
+            self.envelope.volume
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use apu::*;
+use gameboy::CLOCK_SPEED;
+
+/// One sample every 1024 cycles
+const TEST_SAMPLE_RATE: u32 = (CLOCK_SPEED / 1024) as u32;
+
+fn apu(sample_rate: u32) -> Apu {
+    let mut apu = Apu::new(sample_rate);
+    apu.write(NR52_REGISTER, 0x80);
+    apu.write(NR50_REGISTER, 0x77);
+    apu
+}
+
+fn write(apu: &mut Apu, writes: &[(u16, u8)]) {
+    for &(addr, value) in writes {
+        apu.write(addr, value);
+    }
+}
+
+fn render(apu: &mut Apu, cycles: usize) -> (Vec<f32>, Vec<f32>) {
+    apu.step(cycles);
+    let samples = apu.take_samples();
+    let left = samples.iter().step_by(2).cloned().collect();
+    let right = samples.iter().skip(1).step_by(2).cloned().collect();
+    (left, right)
+}
+
+/// Sample of a single channel with `volume` at full master volume
+fn level(volume: u8) -> f32 {
+    volume as f32 / 15.0 / 4.0
+}
+
+#[test]
+fn test_sample_rate() {
+    let mut apu = apu(DEFAULT_SAMPLE_RATE);
+    apu.step(CLOCK_SPEED);
+    assert_eq!(apu.take_samples().len(), 2 * DEFAULT_SAMPLE_RATE as usize);
+    assert!(apu.take_samples().is_empty());
+
+    apu.set_sample_rate(48_000);
+    for _ in 0..CLOCK_SPEED / 16 {
+        apu.step(16);
+    }
+    assert_eq!(apu.take_samples().len(), 2 * 48_000);
+}
+
+#[test]
+fn test_silence() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    let (left, right) = render(&mut apu, 8192);
+    assert_eq!(left, vec![0.0; 8]);
+    assert_eq!(right, vec![0.0; 8]);
+}
+
+#[test]
+fn test_square_duty_cycles() {
+    let patterns = [
+        (0x00, [0, 0, 0, 0, 0, 0, 1, 0]),
+        (0x40, [0, 0, 0, 0, 0, 0, 1, 1]),
+        (0x80, [0, 0, 0, 0, 1, 1, 1, 1]),
+        (0xC0, [1, 1, 1, 1, 1, 1, 0, 0]),
+    ];
+    for &(duty, pattern) in patterns.iter() {
+        // Frequency 1792 advances the duty cycle every 1024 cycles
+        let mut apu = apu(TEST_SAMPLE_RATE);
+        write(
+            &mut apu,
+            &[
+                (NR51_REGISTER, 0x22),
+                (0xFF16, duty),
+                (0xFF17, 0xF0),
+                (0xFF18, 0x00),
+                (0xFF19, 0x87),
+            ],
+        );
+        let expected: Vec<f32> = pattern.iter().map(|&bit| level(15 * bit)).collect();
+        let (left, right) = render(&mut apu, 8192);
+        assert_eq!(left, expected, "NR21 {:02X}", duty);
+        assert_eq!(right, expected, "NR21 {:02X}", duty);
+    }
+}
+
+#[test]
+fn test_panning_and_master_volume() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    write(
+        &mut apu,
+        &[
+            (NR50_REGISTER, 0x03),
+            (NR51_REGISTER, 0x01),
+            (0xFF11, 0xC0),
+            (0xFF12, 0xF0),
+            (0xFF13, 0x00),
+            (0xFF14, 0x87),
+        ],
+    );
+    let (left, right) = render(&mut apu, 1024);
+    assert_eq!(left, vec![0.0]);
+    assert_eq!(right, vec![level(15) * 4.0 / 8.0]);
+}
+
+/// Highest sample of each frame sequencer step
+fn peaks(samples: &[f32]) -> Vec<f32> {
+    samples
+        .chunks(8)
+        .map(|chunk| chunk.iter().cloned().fold(0.0, f32::max))
+        .collect()
+}
+
+#[test]
+fn test_envelope() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    write(
+        &mut apu,
+        &[
+            (NR51_REGISTER, 0x10),
+            (0xFF11, 0xC0),
+            (0xFF12, 0xF1),
+            (0xFF13, 0x00),
+            (0xFF14, 0x87),
+        ],
+    );
+    // The envelope is clocked every 8th frame sequencer step
+    let (left, _) = render(&mut apu, 8192 * 24);
+    let mut expected = vec![level(15); 8];
+    expected.extend(vec![level(14); 8]);
+    expected.extend(vec![level(13); 8]);
+    assert_eq!(peaks(&left), expected);
+}
+
+#[test]
+fn test_envelope_increase() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    write(
+        &mut apu,
+        &[
+            (NR51_REGISTER, 0x10),
+            (0xFF11, 0xC0),
+            (0xFF12, 0x09),
+            (0xFF13, 0x00),
+            (0xFF14, 0x87),
+        ],
+    );
+    let peaks = peaks(&render(&mut apu, 8192 * 8 * 20).0);
+    assert_eq!(peaks[7], level(0));
+    assert_eq!(peaks[8], level(1));
+    assert_eq!(peaks[16], level(2));
+    assert_eq!(*peaks.last().unwrap(), level(15));
+}
+
+#[test]
+fn test_length_counter() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    // Length 63 stops the channel on the first length clock
+    write(&mut apu, &[(0xFF11, 0x3F), (0xFF12, 0xF0), (0xFF14, 0xC7)]);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF1);
+    apu.step(8191);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF1);
+    apu.step(1);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF0);
+
+    // Without length enabled the channel keeps playing
+    write(&mut apu, &[(0xFF11, 0x3F), (0xFF14, 0x87)]);
+    apu.step(8192 * 8);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF1);
+}
+
+#[test]
+fn test_wave_length_counter() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    write(&mut apu, &[(0xFF1A, 0x80), (0xFF1B, 0xFE), (0xFF1E, 0xC0)]);
+    // Length 2 is clocked at frame sequencer steps 0 and 2
+    assert_eq!(apu.read(NR52_REGISTER), 0xF4);
+    apu.step(8192 * 3 - 1);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF4);
+    apu.step(1);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF0);
+}
+
+#[test]
+fn test_dac_off_disables_channel() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    write(&mut apu, &[(0xFF21, 0xF0), (0xFF23, 0x80)]);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF8);
+    apu.write(0xFF21, 0x07);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF0);
+
+    // Triggering does not enable the channel while the DAC is off
+    apu.write(0xFF23, 0x80);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF0);
+}
+
+#[test]
+fn test_sweep() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    // Period 1, increase by frequency / 2 from 1024: the first sweep
+    // clock sets 1536 and the overflow check of 2304 stops the channel
+    write(
+        &mut apu,
+        &[
+            (0xFF10, 0x11),
+            (0xFF12, 0xF0),
+            (0xFF13, 0x00),
+            (0xFF14, 0x84),
+        ],
+    );
+    assert_eq!(apu.read(NR52_REGISTER), 0xF1);
+    apu.step(8192 * 3 - 1);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF1);
+    apu.step(1);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF0);
+}
+
+#[test]
+fn test_sweep_frequency() {
+    // Sweep increases the frequency from 1779 by 1779 >> 7 to 1792,
+    // which advances the 12.5% duty cycle every 1024 cycles
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    write(
+        &mut apu,
+        &[
+            (NR51_REGISTER, 0x10),
+            (0xFF10, 0x17),
+            (0xFF11, 0x00),
+            (0xFF12, 0xF0),
+            (0xFF13, 0xF3),
+            (0xFF14, 0x86),
+        ],
+    );
+    apu.step(8192 * 3);
+    apu.take_samples();
+    assert_eq!(apu.read(NR52_REGISTER), 0xF1);
+    let (left, _) = render(&mut apu, 1024 * 24);
+    let high: Vec<usize> = (0..left.len()).filter(|&i| left[i] > 0.0).collect();
+    assert_eq!(high.len(), 3);
+    assert_eq!(high[1] - high[0], 8);
+    assert_eq!(high[2] - high[1], 8);
+}
+
+#[test]
+fn test_sweep_overflow_on_trigger() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    write(
+        &mut apu,
+        &[
+            (0xFF10, 0x01),
+            (0xFF12, 0xF0),
+            (0xFF13, 0xFF),
+            (0xFF14, 0x86),
+        ],
+    );
+    assert_eq!(apu.read(NR52_REGISTER), 0xF0);
+}
+
+#[test]
+fn test_sweep_negate_quirk() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    write(
+        &mut apu,
+        &[
+            (0xFF10, 0x19),
+            (0xFF12, 0xF0),
+            (0xFF13, 0x00),
+            (0xFF14, 0x84),
+        ],
+    );
+    assert_eq!(apu.read(NR52_REGISTER), 0xF1);
+    // Leaving negate mode after a calculation in it stops the channel
+    apu.write(0xFF10, 0x11);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF0);
+}
+
+#[test]
+fn test_wave_channel() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    // Samples 0-15 twice
+    for i in 0..16 {
+        let sample = (i as u8 * 2) & 0x0F;
+        apu.write(WAVE_RAM_START + i, sample << 4 | (sample + 1));
+    }
+    // Frequency 1536 advances the wave position every 1024 cycles
+    write(
+        &mut apu,
+        &[
+            (NR51_REGISTER, 0x40),
+            (0xFF1A, 0x80),
+            (0xFF1C, 0x20),
+            (0xFF1D, 0x00),
+            (0xFF1E, 0x86),
+        ],
+    );
+    let (left, _) = render(&mut apu, 1024 * 16);
+    let expected: Vec<f32> = (1..17).map(|n| level(n as u8 & 0x0F)).collect();
+    assert_eq!(left, expected);
+
+    // 25% volume shifts the samples right by 2
+    apu.write(0xFF1C, 0x60);
+    let (left, _) = render(&mut apu, 1024 * 4);
+    let expected: Vec<f32> = (17..21).map(|n| level((n as u8 & 0x0F) >> 2)).collect();
+    assert_eq!(left, expected);
+
+    apu.write(0xFF1C, 0x00);
+    let (left, _) = render(&mut apu, 1024 * 4);
+    assert_eq!(left, vec![0.0; 4]);
+}
+
+#[test]
+fn test_noise_channel() {
+    for &(nr43, period) in [(0x00, 0x7FFF), (0x08, 0x7F)].iter() {
+        // Divisor 8 clocks the LFSR every 8 cycles
+        let mut apu = apu((CLOCK_SPEED / 8) as u32);
+        write(
+            &mut apu,
+            &[
+                (NR51_REGISTER, 0x80),
+                (0xFF21, 0xF0),
+                (0xFF22, nr43),
+                (0xFF23, 0x80),
+            ],
+        );
+        let (left, _) = render(&mut apu, 8 * 2 * period);
+        assert!(left.contains(&0.0));
+        assert!(left.contains(&level(15)));
+        assert_eq!(left[..period], left[period..], "NR43 {:02X}", nr43);
+        assert_ne!(left[..period / 2], left[period / 2..period]);
+    }
+}
+
+#[test]
+fn test_power_off() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    write(
+        &mut apu,
+        &[
+            (NR51_REGISTER, 0xFF),
+            (0xFF12, 0xF0),
+            (0xFF14, 0x80),
+            (WAVE_RAM_START, 0x12),
+        ],
+    );
+    assert_eq!(apu.read(NR52_REGISTER), 0xF1);
+
+    apu.write(NR52_REGISTER, 0x00);
+    assert_eq!(apu.read(NR52_REGISTER), 0x70);
+    assert_eq!(apu.read(NR50_REGISTER), 0x00);
+    assert_eq!(apu.read(NR51_REGISTER), 0x00);
+    assert_eq!(apu.read(0xFF12), 0x00);
+    assert_eq!(apu.read(WAVE_RAM_START), 0x12);
+
+    // Writes are ignored while powered off
+    apu.write(NR51_REGISTER, 0xFF);
+    assert_eq!(apu.read(NR51_REGISTER), 0x00);
+    let (left, right) = render(&mut apu, 8192);
+    assert_eq!(left, vec![0.0; 8]);
+    assert_eq!(right, vec![0.0; 8]);
+
+    apu.write(NR52_REGISTER, 0x80);
+    assert_eq!(apu.read(NR52_REGISTER), 0xF0);
+}
+
+#[test]
+fn test_read_masks() {
+    let mut apu = apu(TEST_SAMPLE_RATE);
+    let expected = [
+        0x80, 0x3F, 0x00, 0xFF, 0xBF, 0xFF, 0x3F, 0x00, 0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF,
+        0xFF, 0xFF, 0x00, 0x00, 0xBF, 0x00, 0x00,
+    ];
+    for (i, &mask) in expected.iter().enumerate() {
+        let addr = NR10_REGISTER + i as u16;
+        apu.write(addr, 0x00);
+        assert_eq!(apu.read(addr), mask, "{:04X}", addr);
+    }
+    for addr in 0xFF27..0xFF30 {
+        apu.write(addr, 0x00);
+        assert_eq!(apu.read(addr), 0xFF, "{:04X}", addr);
+    }
+}
@@ -0,0 +1,105 @@
+use super::channel::Length;
+use super::Channel;
+
+/// Channel 3, plays 32 4-bit samples from the wave RAM
+#[derive(Debug)]
+pub struct Wave {
+    pub ram: [u8; 16],
+    dac_enabled: bool,
+    enabled: bool,
+    length: Length,
+    /// Output level shift from NR32
+    volume_code: u8,
+    frequency: u16,
+    timer: u32,
+    position: u8,
+    sample: u8,
+}
+impl Wave {
+    pub fn new(ram: [u8; 16]) -> Wave {
+        Wave {
+            ram,
+            dac_enabled: false,
+            enabled: false,
+            length: Length::new(256),
+            volume_code: 0,
+            frequency: 0,
+            timer: 0,
+            position: 0,
+            sample: 0,
+        }
+    }
+
+    #[inline]
+    fn period(&self) -> u32 {
+        (2048 - self.frequency as u32) * 2
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        self.length.trigger();
+        self.timer = self.period();
+        self.position = 0;
+    }
+}
+
+impl Channel for Wave {
+    fn write(&mut self, register: u8, value: u8) {
+        match register {
+            0 => {
+                self.dac_enabled = value & 0x80 != 0;
+                if !self.dac_enabled {
+                    self.enabled = false;
+                }
+            }
+            1 => self.length.load(value),
+            2 => self.volume_code = (value >> 5) & 0x03,
+            3 => self.frequency = (self.frequency & 0x700) | value as u16,
+            4 => {
+                self.frequency = (self.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
+                self.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+            _ => unreachable!("Wave channel register {}", register),
+        }
+    }
+
+    fn step(&mut self, mut cycles: u32) {
+        while cycles >= self.timer {
+            cycles -= self.timer;
+            self.timer = self.period();
+            self.position = (self.position + 1) & 0x1F;
+            let byte = self.ram[self.position as usize / 2];
+            self.sample = if self.position & 0x01 == 0 {
+                byte >> 4
+            } else {
+                byte & 0x0F
+            };
+        }
+        self.timer -= cycles;
+    }
+
+    fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    fn clock_envelope(&mut self) {}
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+        match self.volume_code {
+            0 => 0,
+            code => self.sample >> (code - 1),
+        }
+    }
+}
@@ -1,58 +1,208 @@
 extern crate gameboy;
+#[macro_use]
+extern crate log;
 extern crate simplelog;
 
+mod cli;
 #[cfg(feature = "glfb")]
 mod gl_display;
+mod headless;
 
 use gameboy::*;
 
-use cpu::Cpu;
-use gpu::Gpu;
-use mmu::Mmu;
+use cli::{Args, Command, Link};
+use display::{Display, Palette};
+use gameboy::gameboy::{CLOCK_SPEED, CYCLES_PER_FRAME};
+use runner::Stop;
+use serial::{CaptureLink, SerialBuffer, SerialLink, TcpLink};
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Cycles between writes of the battery save, about five seconds
+const AUTOSAVE_INTERVAL_IN_CYCLES: usize = 5 * CLOCK_SPEED;
 
 #[cfg(not(feature = "glfb"))]
-fn get_display() -> display::DebugDisplay {
+fn get_display(_palette: Palette, _scale: u32) -> display::DebugDisplay {
     display::DebugDisplay
 }
 
 #[cfg(feature = "glfb")]
-fn get_display() -> gl_display::GlDisplay {
-    gl_display::GlDisplay::new()
+fn get_display(palette: Palette, scale: u32) -> gl_display::GlDisplay {
+    gl_display::GlDisplay::new(palette, scale)
 }
 
-fn main() {
-    let mut args = std::env::args();
-    let filename = args.nth(1);
-    let boot_rom = args.nth(0);
-    simplelog::SimpleLogger::init(
-        simplelog::LevelFilter::Info,
-        simplelog::Config {
-            time: None,
-            target: None,
-            location: None,
-            ..Default::default()
-        },
-    )
-    .unwrap();
-
-    let mut cpu = Cpu::new();
-    let mut gpu = Gpu::new();
-    let mut mmu = Mmu::new(&boot_rom);
-    let mut display = get_display();
-
-    if let Some(filename) = filename {
-        mmu.load_cartridge(&filename).unwrap();
+/// Battery save stored as <rom>.sav
+struct SaveFile {
+    path: PathBuf,
+    saved: Vec<u8>,
+}
+impl SaveFile {
+    /// Loads the save from `dir`, or from next to the ROM if no directory is given
+    fn load<P: AsRef<Path>>(rom: P, dir: Option<&Path>, gameboy: &mut GameBoy) -> SaveFile {
+        let rom = rom.as_ref();
+        let path = match dir {
+            Some(dir) => dir.join(rom.file_name().unwrap_or_default()),
+            None => rom.to_path_buf(),
+        }
+        .with_extension("sav");
+        let mut saved = vec![];
+        if gameboy.has_battery() {
+            if let Ok(data) = fs::read(&path) {
+                info!("Loading save from {}", path.display());
+                gameboy.load_save_data(&data);
+                saved = data;
+            }
+        }
+        SaveFile { path, saved }
+    }
+
+    /// Writes the save file if the cartridge memory has changed
+    fn flush(&mut self, gameboy: &mut GameBoy) {
+        if let Some(data) = gameboy.save_data() {
+            if data != self.saved {
+                match fs::write(&self.path, &data) {
+                    Ok(()) => self.saved = data,
+                    Err(e) => error!("Could not write {}: {}", self.path.display(), e),
+                }
+            }
+        }
+    }
+}
+
+/// Keeps the emulation at `speed` times the speed of the hardware
+struct FrameLimiter {
+    frame_time: Duration,
+    next_frame: Instant,
+}
+impl FrameLimiter {
+    fn new(speed: f64) -> FrameLimiter {
+        let seconds = CYCLES_PER_FRAME as f64 / CLOCK_SPEED as f64 / speed;
+        FrameLimiter {
+            frame_time: Duration::from_secs_f64(seconds),
+            next_frame: Instant::now(),
+        }
+    }
+
+    /// Sleeps until the next frame is due
+    fn wait(&mut self) {
+        self.next_frame += self.frame_time;
+        let now = Instant::now();
+        if self.next_frame > now {
+            thread::sleep(self.next_frame - now);
+        } else if now - self.next_frame > 10 * self.frame_time {
+            // Don't try to catch up after a long pause
+            self.next_frame = now;
+        }
+    }
+}
+
+/// Opens the link cable given with `--link-listen` or `--link-connect`
+fn link_cable(link: &Link) -> Result<Box<dyn SerialLink>, String> {
+    let (link, addr) = match link {
+        Link::Listen(addr) => {
+            info!("Waiting for the link cable on {}", addr);
+            (TcpLink::listen(addr.as_str()), addr)
+        }
+        Link::Connect(addr) => (TcpLink::connect(addr.as_str()), addr),
+    };
+    match link {
+        Ok(link) => Ok(Box::new(link)),
+        Err(e) => Err(format!("Could not open link cable {}: {}", addr, e)),
+    }
+}
+
+fn init_logger(args: &Args) {
+    let config = simplelog::Config {
+        time: None,
+        target: None,
+        location: None,
+        ..Default::default()
+    };
+    // Standard output is reserved for the serial output in headless mode
+    let result = if args.headless.is_some() {
+        simplelog::WriteLogger::init(args.log_level, config, io::stderr())
     } else {
-        mmu.load_cartridge("cpu_instrs/cpu_instrs.gb").unwrap();
+        simplelog::SimpleLogger::init(args.log_level, config)
+    };
+    if let Err(e) = result {
+        eprintln!("Could not initialize logging: {}", e);
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let mut builder = GameBoy::builder().rom(&args.rom).model(args.model);
+    if let Some(ref boot_rom) = args.boot_rom {
+        builder = builder.boot_rom(boot_rom);
+    }
+    if let Some(ref path) = args.trace {
+        let file = File::create(path)
+            .map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+        builder = builder.trace(Box::new(BufWriter::new(file)));
+    }
+    let serial = SerialBuffer::new();
+    if args.headless.is_some() {
+        builder = builder.serial_link(Box::new(CaptureLink::new(serial.clone())));
+    } else if let Some(ref link) = args.link {
+        builder = builder.serial_link(link_cable(link)?);
+    }
+    let mut gameboy = builder
+        .build()
+        .map_err(|e| format!("Could not load {}: {}", args.rom.display(), e))?;
+
+    if let Some(ref options) = args.headless {
+        // Battery saves are neither read nor written to keep the runs repeatable
+        let stop = headless::main(gameboy, &serial, options).map_err(|e| e.to_string())?;
+        let limited = stop == Stop::FrameLimit || stop == Stop::CycleLimit;
+        if limited && (options.stop.until_serial.is_some() || options.stop.until_loop) {
+            process::exit(2);
+        }
+        return Ok(());
     }
 
-    if boot_rom.is_none() {
-        cpu.reset();
+    if let Some(ref dir) = args.save_dir {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
     }
+    let mut save_file = SaveFile::load(&args.rom, args.save_dir.as_deref(), &mut gameboy);
+    let mut display = get_display(args.palette, args.scale);
+    let mut limiter = FrameLimiter::new(args.speed);
 
-    loop {
-        //info!("{}", cpu);
-        cpu.step(&mut mmu);
-        gpu.step(&mut display, &mut mmu, cpu.cycles());
+    let mut next_autosave = AUTOSAVE_INTERVAL_IN_CYCLES;
+    while display.is_running() {
+        gameboy.run_frame();
+        display.render_frame(gameboy.framebuffer());
+        for event in display.button_events() {
+            gameboy.handle_button_event(event);
+        }
+        if gameboy.cycles() >= next_autosave {
+            next_autosave += AUTOSAVE_INTERVAL_IN_CYCLES;
+            save_file.flush(&mut gameboy);
+        }
+        limiter.wait();
+    }
+    save_file.flush(&mut gameboy);
+    Ok(())
+}
+
+fn main() {
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(args)) => args,
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            process::exit(1);
+        }
+    };
+    init_logger(&args);
+    if let Err(e) = run(*args) {
+        eprintln!("error: {}", e);
+        process::exit(1);
     }
 }
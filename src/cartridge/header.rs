@@ -0,0 +1,284 @@
+use std::error;
+use std::fmt;
+
+use super::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+pub const HEADER_END: usize = 0x150;
+
+const LOGO_START: usize = 0x104;
+const TITLE_START: usize = 0x134;
+const MANUFACTURER_START: usize = 0x13F;
+const CGB_FLAG: usize = 0x143;
+const NEW_LICENSEE_START: usize = 0x144;
+const SGB_FLAG: usize = 0x146;
+const CARTRIDGE_TYPE: usize = 0x147;
+const ROM_SIZE: usize = 0x148;
+const RAM_SIZE: usize = 0x149;
+const DESTINATION: usize = 0x14A;
+const OLD_LICENSEE: usize = 0x14B;
+const VERSION: usize = 0x14C;
+const HEADER_CHECKSUM: usize = 0x14D;
+const GLOBAL_CHECKSUM: usize = 0x14E;
+
+/// The logo the boot ROM compares against before starting the game
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+#[derive(Debug, PartialEq)]
+pub enum HeaderError {
+    /// The ROM is too small to contain a header
+    TooShort(usize),
+    InvalidLogo,
+    InvalidHeaderChecksum {
+        expected: u8,
+        actual: u8,
+    },
+    InvalidRomSize(u8),
+    InvalidRamSize(u8),
+}
+impl fmt::Display for HeaderError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeaderError::TooShort(len) => {
+                write!(
+                    fmt,
+                    "ROM is {} bytes, too short for a cartridge header",
+                    len
+                )
+            }
+            HeaderError::InvalidLogo => write!(fmt, "Nintendo logo does not match"),
+            HeaderError::InvalidHeaderChecksum { expected, actual } => write!(
+                fmt,
+                "header checksum is 0x{:02X}, expected 0x{:02X}",
+                actual, expected
+            ),
+            HeaderError::InvalidRomSize(code) => write!(fmt, "invalid ROM size 0x{:02X}", code),
+            HeaderError::InvalidRamSize(code) => write!(fmt, "invalid RAM size 0x{:02X}", code),
+        }
+    }
+}
+impl error::Error for HeaderError {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CgbSupport {
+    DmgOnly,
+    /// Works on both DMG and CGB
+    Enhanced,
+    CgbOnly,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Licensee {
+    Old(u8),
+    /// Two ASCII characters, used when the old code is 0x33
+    New(String),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Destination {
+    Japan,
+    Overseas,
+}
+
+/// Cartridge header at 0x100-0x14F
+#[derive(Clone, Debug)]
+pub struct Header {
+    pub title: String,
+    pub manufacturer_code: Option<String>,
+    pub cgb: CgbSupport,
+    pub sgb: bool,
+    pub licensee: Licensee,
+    pub cartridge_type: u8,
+    /// ROM size in bytes
+    pub rom_size: usize,
+    /// External RAM size in bytes
+    pub ram_size: usize,
+    pub destination: Destination,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+    /// The global checksum is not verified by the hardware,
+    /// so a mismatch is not an error.
+    pub global_checksum_valid: bool,
+}
+
+fn ascii(data: &[u8]) -> String {
+    data.iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| {
+            if c.is_ascii_graphic() || c == b' ' {
+                c as char
+            } else {
+                '?'
+            }
+        })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+pub fn header_checksum(rom: &[u8]) -> u8 {
+    rom[TITLE_START..HEADER_CHECKSUM]
+        .iter()
+        .fold(0u8, |x, &b| x.wrapping_sub(b).wrapping_sub(1))
+}
+
+pub fn global_checksum(rom: &[u8]) -> u16 {
+    rom.iter()
+        .enumerate()
+        .filter(|&(i, _)| i != GLOBAL_CHECKSUM && i != GLOBAL_CHECKSUM + 1)
+        .fold(0u16, |x, (_, &b)| x.wrapping_add(b as u16))
+}
+
+fn rom_size(code: u8) -> Result<usize, HeaderError> {
+    match code {
+        0x00..=0x08 => Ok((2 * ROM_BANK_SIZE) << code),
+        code => Err(HeaderError::InvalidRomSize(code)),
+    }
+}
+
+fn ram_size(code: u8) -> Result<usize, HeaderError> {
+    match code {
+        0x00 => Ok(0),
+        0x01 => Ok(0x800),
+        0x02 => Ok(RAM_BANK_SIZE),
+        0x03 => Ok(4 * RAM_BANK_SIZE),
+        0x04 => Ok(16 * RAM_BANK_SIZE),
+        0x05 => Ok(8 * RAM_BANK_SIZE),
+        code => Err(HeaderError::InvalidRamSize(code)),
+    }
+}
+
+pub fn cartridge_type_name(cartridge_type: u8) -> &'static str {
+    match cartridge_type {
+        0x00 => "ROM ONLY",
+        0x01 => "MBC1",
+        0x02 => "MBC1+RAM",
+        0x03 => "MBC1+RAM+BATTERY",
+        0x05 => "MBC2",
+        0x06 => "MBC2+BATTERY",
+        0x08 => "ROM+RAM",
+        0x09 => "ROM+RAM+BATTERY",
+        0x0B => "MMM01",
+        0x0C => "MMM01+RAM",
+        0x0D => "MMM01+RAM+BATTERY",
+        0x0F => "MBC3+TIMER+BATTERY",
+        0x10 => "MBC3+TIMER+RAM+BATTERY",
+        0x11 => "MBC3",
+        0x12 => "MBC3+RAM",
+        0x13 => "MBC3+RAM+BATTERY",
+        0x19 => "MBC5",
+        0x1A => "MBC5+RAM",
+        0x1B => "MBC5+RAM+BATTERY",
+        0x1C => "MBC5+RUMBLE",
+        0x1D => "MBC5+RUMBLE+RAM",
+        0x1E => "MBC5+RUMBLE+RAM+BATTERY",
+        0x20 => "MBC6",
+        0x22 => "MBC7+SENSOR+RUMBLE+RAM+BATTERY",
+        0xFC => "POCKET CAMERA",
+        0xFD => "BANDAI TAMA5",
+        0xFE => "HuC3",
+        0xFF => "HuC1+RAM+BATTERY",
+        _ => "UNKNOWN",
+    }
+}
+
+impl Header {
+    pub fn parse(rom: &[u8]) -> Result<Header, HeaderError> {
+        if rom.len() < HEADER_END {
+            return Err(HeaderError::TooShort(rom.len()));
+        }
+        if rom[LOGO_START..TITLE_START] != NINTENDO_LOGO[..] {
+            return Err(HeaderError::InvalidLogo);
+        }
+        let expected = header_checksum(rom);
+        if rom[HEADER_CHECKSUM] != expected {
+            return Err(HeaderError::InvalidHeaderChecksum {
+                expected,
+                actual: rom[HEADER_CHECKSUM],
+            });
+        }
+
+        let cgb = match rom[CGB_FLAG] {
+            0x80 => CgbSupport::Enhanced,
+            0xC0 => CgbSupport::CgbOnly,
+            _ => CgbSupport::DmgOnly,
+        };
+        // Newer cartridges use the end of the title area
+        // for the manufacturer code and the CGB flag
+        let (title, manufacturer_code) = match cgb {
+            CgbSupport::DmgOnly => (ascii(&rom[TITLE_START..CGB_FLAG + 1]), None),
+            _ => {
+                let manufacturer = &rom[MANUFACTURER_START..CGB_FLAG];
+                if manufacturer.iter().all(|c| c.is_ascii_uppercase()) {
+                    (
+                        ascii(&rom[TITLE_START..MANUFACTURER_START]),
+                        Some(ascii(manufacturer)),
+                    )
+                } else {
+                    (ascii(&rom[TITLE_START..CGB_FLAG]), None)
+                }
+            }
+        };
+        let licensee = match rom[OLD_LICENSEE] {
+            0x33 => Licensee::New(ascii(&rom[NEW_LICENSEE_START..SGB_FLAG])),
+            code => Licensee::Old(code),
+        };
+        let global_checksum = (rom[GLOBAL_CHECKSUM] as u16) << 8 | rom[GLOBAL_CHECKSUM + 1] as u16;
+
+        Ok(Header {
+            title,
+            manufacturer_code,
+            cgb,
+            // The SGB functions are only available with the old licensee 0x33
+            sgb: rom[SGB_FLAG] == 0x03 && rom[OLD_LICENSEE] == 0x33,
+            licensee,
+            cartridge_type: rom[CARTRIDGE_TYPE],
+            rom_size: rom_size(rom[ROM_SIZE])?,
+            ram_size: ram_size(rom[RAM_SIZE])?,
+            destination: if rom[DESTINATION] == 0 {
+                Destination::Japan
+            } else {
+                Destination::Overseas
+            },
+            version: rom[VERSION],
+            header_checksum: rom[HEADER_CHECKSUM],
+            global_checksum,
+            global_checksum_valid: global_checksum == self::global_checksum(rom),
+        })
+    }
+}
+
+impl fmt::Display for Header {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "\"{}\"", self.title)?;
+        if let Some(ref code) = self.manufacturer_code {
+            write!(fmt, " ({})", code)?;
+        }
+        write!(
+            fmt,
+            ", {} (0x{:02X}), ROM {}KB, RAM {}KB",
+            cartridge_type_name(self.cartridge_type),
+            self.cartridge_type,
+            self.rom_size / 1024,
+            self.ram_size / 1024
+        )?;
+        match self.licensee {
+            Licensee::Old(code) => write!(fmt, ", licensee 0x{:02X}", code)?,
+            Licensee::New(ref code) => write!(fmt, ", licensee \"{}\"", code)?,
+        }
+        write!(fmt, ", {:?}, version {}", self.destination, self.version)?;
+        match self.cgb {
+            CgbSupport::DmgOnly => {}
+            CgbSupport::Enhanced => write!(fmt, ", CGB enhanced")?,
+            CgbSupport::CgbOnly => write!(fmt, ", CGB only")?,
+        }
+        if self.sgb {
+            write!(fmt, ", SGB")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use super::{rom_banks, Cartridge, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BankingMode {
+    /// 0x0000-0x3FFF and 0xA000-0xBFFF are always mapped to bank 0
+    Simple,
+    /// The upper bank register also selects the bank of
+    /// 0x0000-0x3FFF and the RAM bank of 0xA000-0xBFFF
+    Advanced,
+}
+
+/// MBC1 memory bank controller with up to 2MB of ROM and 32KB of RAM
+pub struct MBC1 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    ram_enabled: bool,
+    /// Lower 5 bits of the ROM bank number
+    rom_bank: u8,
+    /// RAM bank number or upper 2 bits of the ROM bank number
+    upper_bank: u8,
+    mode: BankingMode,
+}
+impl MBC1 {
+    pub fn new(rom: Vec<u8>, ram_size: usize) -> MBC1 {
+        info!("Cartridge type is MBC1");
+        MBC1 {
+            rom: rom_banks(rom),
+            ram: vec![0; ram_size].into_boxed_slice(),
+            ram_enabled: false,
+            rom_bank: 1,
+            upper_bank: 0,
+            mode: BankingMode::Simple,
+        }
+    }
+
+    #[inline]
+    fn rom_bank_count(&self) -> usize {
+        self.rom.len() / ROM_BANK_SIZE
+    }
+
+    /// Bank mapped to 0x0000-0x3FFF
+    fn lower_rom_bank(&self) -> usize {
+        match self.mode {
+            BankingMode::Simple => 0,
+            BankingMode::Advanced => ((self.upper_bank as usize) << 5) % self.rom_bank_count(),
+        }
+    }
+
+    /// Bank mapped to 0x4000-0x7FFF
+    fn upper_rom_bank(&self) -> usize {
+        let bank = (self.upper_bank as usize) << 5 | self.rom_bank as usize;
+        bank % self.rom_bank_count()
+    }
+
+    fn ram_offset(&self, addr: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return None;
+        }
+        let bank = match self.mode {
+            BankingMode::Simple => 0,
+            BankingMode::Advanced => self.upper_bank as usize,
+        };
+        let offset = bank * RAM_BANK_SIZE + (addr - 0xA000) as usize;
+        Some(offset % self.ram.len())
+    }
+}
+
+impl Cartridge for MBC1 {
+    fn write_u8(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = value & 0x0F == 0x0A;
+            }
+            0x2000..=0x3FFF => {
+                // Bank 0 can't be selected, the controller maps it to bank 1.
+                // The check is done with the 5 bits of the register only,
+                // which makes banks 0x20, 0x40 and 0x60 unreachable as well.
+                self.rom_bank = match value & 0x1F {
+                    0 => 1,
+                    bank => bank,
+                };
+            }
+            0x4000..=0x5FFF => {
+                self.upper_bank = value & 0x03;
+            }
+            0x6000..=0x7FFF => {
+                self.mode = if value & 0x01 == 0 {
+                    BankingMode::Simple
+                } else {
+                    BankingMode::Advanced
+                };
+            }
+            0xA000..=0xBFFF => {
+                if let Some(offset) = self.ram_offset(addr) {
+                    self.ram[offset] = value;
+                }
+            }
+            _ => unreachable!("MBC1 write outside of cartridge area: {:04X}", addr),
+        }
+    }
+    fn read_u8(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom[self.lower_rom_bank() * ROM_BANK_SIZE + addr as usize],
+            0x4000..=0x7FFF => {
+                self.rom[self.upper_rom_bank() * ROM_BANK_SIZE + (addr - 0x4000) as usize]
+            }
+            0xA000..=0xBFFF => match self.ram_offset(addr) {
+                Some(offset) => self.ram[offset],
+                None => 0xFF,
+            },
+            _ => unreachable!("MBC1 read outside of cartridge area: {:04X}", addr),
+        }
+    }
+    fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+}
@@ -0,0 +1,79 @@
+use super::{rom_banks, Cartridge, ROM_BANK_SIZE};
+
+/// Size of the built-in RAM, every byte holds a 4-bit value
+const RAM_SIZE: usize = 512;
+
+/// MBC2 memory bank controller with up to 256KB of ROM and 512x4 bits of RAM
+pub struct MBC2 {
+    rom: Box<[u8]>,
+    ram: [u8; RAM_SIZE],
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+impl MBC2 {
+    pub fn new(rom: Vec<u8>) -> MBC2 {
+        info!("Cartridge type is MBC2");
+        MBC2 {
+            rom: rom_banks(rom),
+            ram: [0; RAM_SIZE],
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+
+    #[inline]
+    fn rom_bank_count(&self) -> usize {
+        self.rom.len() / ROM_BANK_SIZE
+    }
+}
+
+impl Cartridge for MBC2 {
+    fn write_u8(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x3FFF => {
+                // The least significant bit of the upper address byte
+                // selects between RAM enable and ROM bank registers
+                if addr & 0x100 == 0 {
+                    self.ram_enabled = value & 0x0F == 0x0A;
+                } else {
+                    self.rom_bank = match value & 0x0F {
+                        0 => 1,
+                        bank => bank,
+                    };
+                }
+            }
+            0x4000..=0x7FFF => {}
+            0xA000..=0xBFFF => {
+                if self.ram_enabled {
+                    // The RAM is echoed through the whole area
+                    self.ram[addr as usize % RAM_SIZE] = value & 0x0F;
+                }
+            }
+            _ => unreachable!("MBC2 write outside of cartridge area: {:04X}", addr),
+        }
+    }
+    fn read_u8(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom[addr as usize],
+            0x4000..=0x7FFF => {
+                let bank = self.rom_bank as usize % self.rom_bank_count();
+                self.rom[bank * ROM_BANK_SIZE + (addr - 0x4000) as usize]
+            }
+            0xA000..=0xBFFF => {
+                if self.ram_enabled {
+                    // Only the lower 4 bits are connected
+                    self.ram[addr as usize % RAM_SIZE] | 0xF0
+                } else {
+                    0xFF
+                }
+            }
+            _ => unreachable!("MBC2 read outside of cartridge area: {:04X}", addr),
+        }
+    }
+    fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+}
@@ -0,0 +1,128 @@
+use super::rtc::{Rtc, RTC_FOOTER_SIZE};
+use super::{rom_banks, Cartridge, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+/// MBC3 memory bank controller with up to 2MB of ROM and 32KB of RAM
+pub struct MBC3 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    ram_enabled: bool,
+    rom_bank: u8,
+    /// RAM bank number, or a RTC register with values 0x08-0x0C
+    ram_bank: u8,
+    rtc: Option<Rtc>,
+}
+impl MBC3 {
+    pub fn new(rom: Vec<u8>, ram_size: usize, rtc: Option<Rtc>) -> MBC3 {
+        info!("Cartridge type is MBC3");
+        MBC3 {
+            rom: rom_banks(rom),
+            ram: vec![0; ram_size].into_boxed_slice(),
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            rtc,
+        }
+    }
+
+    pub fn rtc(&mut self) -> Option<&mut Rtc> {
+        self.rtc.as_mut()
+    }
+
+    /// RTC register selected in place of a RAM bank
+    fn rtc_register(&self) -> Option<u8> {
+        match self.ram_bank {
+            0x08..=0x0C if self.ram_enabled && self.rtc.is_some() => Some(self.ram_bank),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn rom_bank_count(&self) -> usize {
+        self.rom.len() / ROM_BANK_SIZE
+    }
+
+    fn ram_offset(&self, addr: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram.is_empty() || self.ram_bank > 0x07 {
+            return None;
+        }
+        let offset = self.ram_bank as usize * RAM_BANK_SIZE + (addr - 0xA000) as usize;
+        Some(offset % self.ram.len())
+    }
+}
+
+impl Cartridge for MBC3 {
+    fn write_u8(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = value & 0x0F == 0x0A;
+            }
+            0x2000..=0x3FFF => {
+                self.rom_bank = match value & 0x7F {
+                    0 => 1,
+                    bank => bank,
+                };
+            }
+            0x4000..=0x5FFF => {
+                self.ram_bank = value & 0x0F;
+            }
+            0x6000..=0x7FFF => {
+                if let Some(ref mut rtc) = self.rtc {
+                    rtc.write_latch(value);
+                }
+            }
+            0xA000..=0xBFFF => {
+                if let Some(register) = self.rtc_register() {
+                    if let Some(ref mut rtc) = self.rtc {
+                        rtc.write(register, value);
+                    }
+                } else if let Some(offset) = self.ram_offset(addr) {
+                    self.ram[offset] = value;
+                }
+            }
+            _ => unreachable!("MBC3 write outside of cartridge area: {:04X}", addr),
+        }
+    }
+    fn read_u8(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom[addr as usize],
+            0x4000..=0x7FFF => {
+                let bank = self.rom_bank as usize % self.rom_bank_count();
+                self.rom[bank * ROM_BANK_SIZE + (addr - 0x4000) as usize]
+            }
+            0xA000..=0xBFFF => {
+                if let (Some(register), Some(rtc)) = (self.rtc_register(), self.rtc.as_ref()) {
+                    return rtc.read(register);
+                }
+                match self.ram_offset(addr) {
+                    Some(offset) => self.ram[offset],
+                    None => 0xFF,
+                }
+            }
+            _ => unreachable!("MBC3 read outside of cartridge area: {:04X}", addr),
+        }
+    }
+    fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+    fn save_data(&mut self) -> Vec<u8> {
+        let mut data = self.ram.to_vec();
+        if let Some(ref mut rtc) = self.rtc {
+            data.extend_from_slice(&rtc.footer());
+        }
+        data
+    }
+    fn load_save_data(&mut self, data: &[u8]) {
+        let len = self.ram.len().min(data.len());
+        self.ram[..len].copy_from_slice(&data[..len]);
+        if let Some(ref mut rtc) = self.rtc {
+            if data.len() == len + RTC_FOOTER_SIZE {
+                rtc.load_footer(&data[len..]);
+            } else {
+                warn!("Save data has no RTC footer");
+            }
+        }
+    }
+}
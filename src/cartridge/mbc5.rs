@@ -0,0 +1,95 @@
+use super::{rom_banks, Cartridge, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+/// MBC5 memory bank controller with up to 8MB of ROM and 128KB of RAM
+pub struct MBC5 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    ram_enabled: bool,
+    /// 9-bit ROM bank number, bank 0 can be mapped to 0x4000-0x7FFF
+    rom_bank: u16,
+    ram_bank: u8,
+    has_rumble: bool,
+    rumble: bool,
+}
+impl MBC5 {
+    pub fn new(rom: Vec<u8>, ram_size: usize, has_rumble: bool) -> MBC5 {
+        info!("Cartridge type is MBC5");
+        MBC5 {
+            rom: rom_banks(rom),
+            ram: vec![0; ram_size].into_boxed_slice(),
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            rumble: false,
+        }
+    }
+
+    #[inline]
+    fn rom_bank_count(&self) -> usize {
+        self.rom.len() / ROM_BANK_SIZE
+    }
+
+    fn ram_offset(&self, addr: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return None;
+        }
+        let offset = self.ram_bank as usize * RAM_BANK_SIZE + (addr - 0xA000) as usize;
+        Some(offset % self.ram.len())
+    }
+}
+
+impl Cartridge for MBC5 {
+    fn write_u8(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = value & 0x0F == 0x0A;
+            }
+            0x2000..=0x2FFF => {
+                self.rom_bank = (self.rom_bank & 0x100) | value as u16;
+            }
+            0x3000..=0x3FFF => {
+                self.rom_bank = (self.rom_bank & 0xFF) | ((value as u16 & 0x01) << 8);
+            }
+            0x4000..=0x5FFF => {
+                if self.has_rumble {
+                    // Bit 3 drives the rumble motor instead of the RAM bank
+                    self.rumble = value & 0x08 != 0;
+                    self.ram_bank = value & 0x07;
+                } else {
+                    self.ram_bank = value & 0x0F;
+                }
+            }
+            0x6000..=0x7FFF => {}
+            0xA000..=0xBFFF => {
+                if let Some(offset) = self.ram_offset(addr) {
+                    self.ram[offset] = value;
+                }
+            }
+            _ => unreachable!("MBC5 write outside of cartridge area: {:04X}", addr),
+        }
+    }
+    fn read_u8(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom[addr as usize],
+            0x4000..=0x7FFF => {
+                let bank = self.rom_bank as usize % self.rom_bank_count();
+                self.rom[bank * ROM_BANK_SIZE + (addr - 0x4000) as usize]
+            }
+            0xA000..=0xBFFF => match self.ram_offset(addr) {
+                Some(offset) => self.ram[offset],
+                None => 0xFF,
+            },
+            _ => unreachable!("MBC5 read outside of cartridge area: {:04X}", addr),
+        }
+    }
+    fn rumble(&self) -> bool {
+        self.rumble
+    }
+    fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+}
@@ -0,0 +1,71 @@
+mod header;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod rom_only;
+pub mod rtc;
+#[cfg(test)]
+pub mod tests;
+
+pub use self::header::{CgbSupport, Destination, Header, HeaderError, Licensee};
+pub use self::mbc1::MBC1;
+pub use self::mbc2::MBC2;
+pub use self::mbc3::MBC3;
+pub use self::mbc5::MBC5;
+pub use self::rom_only::RomOnly;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+/// Memory bank controller of a cartridge.
+///
+/// The Mmu forwards both the ROM area (0x0000-0x7FFF) and the external
+/// RAM area (0xA000-0xBFFF) to the cartridge. Writes to the ROM area
+/// are used to program the controller registers.
+pub trait Cartridge {
+    fn write_u8(&mut self, addr: u16, value: u8);
+    fn read_u8(&self, addr: u16) -> u8;
+    /// State of the rumble motor on cartridges that have one
+    fn rumble(&self) -> bool {
+        false
+    }
+    /// External RAM of the cartridge
+    fn ram(&self) -> &[u8] {
+        &[]
+    }
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut []
+    }
+    /// Contents of the battery-backed memory in the .sav file format
+    fn save_data(&mut self) -> Vec<u8> {
+        self.ram().to_vec()
+    }
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram = self.ram_mut();
+        if data.len() < ram.len() {
+            warn!("Save data is {} bytes, expected {}", data.len(), ram.len());
+        }
+        let len = ram.len().min(data.len());
+        ram[..len].copy_from_slice(&data[..len]);
+    }
+}
+
+/// Whether the cartridge type at 0x147 has a battery to keep RAM contents
+///
+/// Of the MBC3 types 0x0F-0x13 only 0x0F, 0x10 and 0x13 have a battery,
+/// plain MBC3 (0x11) and MBC3+RAM (0x12) carts lose their RAM at power off.
+pub fn has_battery(cartridge_type: u8) -> bool {
+    matches!(
+        cartridge_type,
+        0x03 | 0x06 | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E
+    )
+}
+
+/// Pads the ROM image to a whole number of 16KB banks,
+/// but at least the two banks that are always mapped.
+fn rom_banks(mut rom: Vec<u8>) -> Box<[u8]> {
+    let banks = rom.len().div_ceil(ROM_BANK_SIZE).max(2);
+    rom.resize(banks * ROM_BANK_SIZE, 0xFF);
+    rom.into_boxed_slice()
+}
@@ -0,0 +1,24 @@
+use super::Cartridge;
+
+/// Cartridge without a memory bank controller, 32KB of ROM and no RAM
+pub struct RomOnly {
+    rom: Box<[u8]>,
+}
+impl RomOnly {
+    pub fn new(mut rom: Vec<u8>) -> RomOnly {
+        rom.resize(0x8000, 0xFF);
+        RomOnly {
+            rom: rom.into_boxed_slice(),
+        }
+    }
+}
+
+impl Cartridge for RomOnly {
+    fn write_u8(&mut self, _addr: u16, _value: u8) {}
+    fn read_u8(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x7FFF => self.rom[addr as usize],
+            _ => 0xFF,
+        }
+    }
+}
@@ -0,0 +1,200 @@
+/// Size of the RTC footer appended to the battery save.
+///
+/// The layout is the one used by VBA-M, BGB and most other emulators:
+/// five little-endian u32 values for the seconds, minutes, hours, day low
+/// and day high registers, the same five for the latched registers, and
+/// a little-endian u64 UNIX timestamp of the moment the save was written.
+pub const RTC_FOOTER_SIZE: usize = 48;
+
+const DH_DAY_BIT8: u8 = 0x01;
+const DH_HALT: u8 = 0x40;
+const DH_CARRY: u8 = 0x80;
+
+/// Source of wall clock time for the RTC
+pub trait Clock {
+    /// Seconds since the UNIX epoch
+    fn now(&self) -> u64;
+}
+
+pub struct SystemClock;
+#[cfg(not(target_os = "unknown"))]
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
+#[cfg(target_os = "unknown")]
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        use wasm_bindgen::prelude::*;
+        #[wasm_bindgen]
+        extern "C" {
+            #[wasm_bindgen(js_namespace = Date, js_name = now)]
+            fn date_now() -> f64;
+        }
+        (date_now() / 1000.0) as u64
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RtcRegisters {
+    pub seconds: u8,
+    pub minutes: u8,
+    pub hours: u8,
+    pub day_low: u8,
+    pub day_high: u8,
+}
+impl RtcRegisters {
+    fn read(&self, register: u8) -> u8 {
+        match register {
+            0x08 => self.seconds,
+            0x09 => self.minutes,
+            0x0A => self.hours,
+            0x0B => self.day_low,
+            0x0C => self.day_high,
+            _ => 0xFF,
+        }
+    }
+    fn days(&self) -> u64 {
+        ((self.day_high & DH_DAY_BIT8) as u64) << 8 | self.day_low as u64
+    }
+    fn halted(&self) -> bool {
+        self.day_high & DH_HALT != 0
+    }
+    fn to_footer(self, out: &mut [u8]) {
+        let values = [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.day_low,
+            self.day_high,
+        ];
+        for (i, value) in values.iter().enumerate() {
+            out[i * 4..i * 4 + 4].copy_from_slice(&(*value as u32).to_le_bytes());
+        }
+    }
+    fn from_footer(data: &[u8]) -> RtcRegisters {
+        let value = |i: usize| data[i * 4];
+        RtcRegisters {
+            seconds: value(0) & 0x3F,
+            minutes: value(1) & 0x3F,
+            hours: value(2) & 0x1F,
+            day_low: value(3),
+            day_high: value(4) & (DH_DAY_BIT8 | DH_HALT | DH_CARRY),
+        }
+    }
+}
+
+/// Real-time clock of MBC3 cartridges
+pub struct Rtc {
+    clock: Box<dyn Clock>,
+    registers: RtcRegisters,
+    latched: RtcRegisters,
+    /// Timestamp up to which `registers` have been advanced
+    last_update: u64,
+    /// 0x00 has been written to the latch register
+    latch_armed: bool,
+}
+impl Rtc {
+    pub fn new(clock: Box<dyn Clock>) -> Rtc {
+        let last_update = clock.now();
+        Rtc {
+            clock,
+            registers: RtcRegisters::default(),
+            latched: RtcRegisters::default(),
+            last_update,
+            latch_armed: false,
+        }
+    }
+
+    pub fn registers(&mut self) -> RtcRegisters {
+        self.update();
+        self.registers
+    }
+
+    pub fn latched(&self) -> RtcRegisters {
+        self.latched
+    }
+
+    /// Advances the clock registers to the current time
+    fn update(&mut self) {
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last_update);
+        self.last_update = now;
+        if elapsed == 0 || self.registers.halted() {
+            return;
+        }
+
+        let r = &mut self.registers;
+        let seconds = r.seconds as u64 + elapsed;
+        let minutes = r.minutes as u64 + seconds / 60;
+        let hours = r.hours as u64 + minutes / 60;
+        let mut days = r.days() + hours / 24;
+        if days > 0x1FF {
+            r.day_high |= DH_CARRY;
+            days &= 0x1FF;
+        }
+        r.seconds = (seconds % 60) as u8;
+        r.minutes = (minutes % 60) as u8;
+        r.hours = (hours % 24) as u8;
+        r.day_low = (days & 0xFF) as u8;
+        r.day_high = (r.day_high & !DH_DAY_BIT8) | (days >> 8) as u8;
+    }
+
+    /// Handles writes to the 0x6000-0x7FFF latch register.
+    /// Writing 0x00 followed by 0x01 latches the current time.
+    pub fn write_latch(&mut self, value: u8) {
+        if self.latch_armed && value == 0x01 {
+            self.update();
+            self.latched = self.registers;
+        }
+        self.latch_armed = value == 0x00;
+    }
+
+    pub fn read(&self, register: u8) -> u8 {
+        self.latched.read(register)
+    }
+
+    pub fn write(&mut self, register: u8, value: u8) {
+        self.update();
+        let r = &mut self.registers;
+        match register {
+            0x08 => r.seconds = value & 0x3F,
+            0x09 => r.minutes = value & 0x3F,
+            0x0A => r.hours = value & 0x1F,
+            0x0B => r.day_low = value,
+            0x0C => r.day_high = value & (DH_DAY_BIT8 | DH_HALT | DH_CARRY),
+            _ => {}
+        }
+    }
+
+    /// Current state of the clock in the 48-byte footer format
+    pub fn footer(&mut self) -> [u8; RTC_FOOTER_SIZE] {
+        self.update();
+        let mut footer = [0; RTC_FOOTER_SIZE];
+        self.registers.to_footer(&mut footer[0..20]);
+        self.latched.to_footer(&mut footer[20..40]);
+        footer[40..48].copy_from_slice(&self.last_update.to_le_bytes());
+        footer
+    }
+
+    /// Restores the clock from a footer written by `footer`.
+    /// The time that has passed since the footer was saved
+    /// is added to the clock registers.
+    pub fn load_footer(&mut self, footer: &[u8]) {
+        if footer.len() != RTC_FOOTER_SIZE {
+            warn!("Ignoring RTC footer of {} bytes", footer.len());
+            return;
+        }
+        self.registers = RtcRegisters::from_footer(&footer[0..20]);
+        self.latched = RtcRegisters::from_footer(&footer[20..40]);
+        let mut timestamp = [0; 8];
+        timestamp.copy_from_slice(&footer[40..48]);
+        self.last_update = u64::from_le_bytes(timestamp);
+        self.update();
+    }
+}
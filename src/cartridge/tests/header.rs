@@ -0,0 +1,137 @@
+use cartridge::header::{global_checksum, header_checksum};
+use cartridge::tests::*;
+use error::GameboyError;
+use mmu::Mmu;
+
+fn fix_checksums(rom: &mut [u8]) {
+    rom[0x14D] = header_checksum(rom);
+    let global = global_checksum(rom);
+    rom[0x14E] = (global >> 8) as u8;
+    rom[0x14F] = (global & 0xFF) as u8;
+}
+
+#[test]
+fn test_header_fields() {
+    let mut rom = cartridge_rom(64, 0x13, 0x03);
+    rom[0x14A] = 0x01;
+    rom[0x14B] = 0x01;
+    rom[0x14C] = 0x02;
+    fix_checksums(&mut rom);
+
+    let header = Header::parse(&rom).unwrap();
+    assert_eq!(header.title, "TESTCART");
+    assert_eq!(header.manufacturer_code, None);
+    assert_eq!(header.cgb, CgbSupport::DmgOnly);
+    assert!(!header.sgb);
+    assert_eq!(header.licensee, Licensee::Old(0x01));
+    assert_eq!(header.cartridge_type, 0x13);
+    assert_eq!(header.rom_size, 1024 * 1024);
+    assert_eq!(header.ram_size, 32 * 1024);
+    assert_eq!(header.destination, Destination::Overseas);
+    assert_eq!(header.version, 2);
+    assert_eq!(header.header_checksum, rom[0x14D]);
+    assert!(header.global_checksum_valid);
+}
+
+#[test]
+fn test_header_cgb_title_and_manufacturer() {
+    let mut rom = cartridge_rom(2, 0x00, 0x00);
+    rom[0x134..0x144].copy_from_slice(b"POKEMON_SLVAAXE\x80");
+    rom[0x144..0x146].copy_from_slice(b"01");
+    rom[0x146] = 0x03;
+    rom[0x14B] = 0x33;
+    fix_checksums(&mut rom);
+
+    let header = Header::parse(&rom).unwrap();
+    assert_eq!(header.title, "POKEMON_SLV");
+    assert_eq!(header.manufacturer_code, Some("AAXE".to_string()));
+    assert_eq!(header.cgb, CgbSupport::Enhanced);
+    assert!(header.sgb);
+    assert_eq!(header.licensee, Licensee::New("01".to_string()));
+    assert_eq!(header.destination, Destination::Japan);
+
+    rom[0x143] = 0xC0;
+    fix_checksums(&mut rom);
+    assert_eq!(Header::parse(&rom).unwrap().cgb, CgbSupport::CgbOnly);
+}
+
+#[test]
+fn test_header_too_short() {
+    let rom = cartridge_rom(2, 0x00, 0x00);
+    assert_eq!(
+        Header::parse(&rom[0..0x14F]).unwrap_err(),
+        HeaderError::TooShort(0x14F)
+    );
+}
+
+#[test]
+fn test_header_invalid_logo() {
+    let mut rom = cartridge_rom(2, 0x00, 0x00);
+    rom[0x120] ^= 0xFF;
+    assert_eq!(Header::parse(&rom).unwrap_err(), HeaderError::InvalidLogo);
+}
+
+#[test]
+fn test_header_invalid_checksum() {
+    let mut rom = cartridge_rom(2, 0x00, 0x00);
+    let expected = rom[0x14D];
+    rom[0x14D] = expected.wrapping_add(1);
+    assert_eq!(
+        Header::parse(&rom).unwrap_err(),
+        HeaderError::InvalidHeaderChecksum {
+            expected,
+            actual: expected.wrapping_add(1)
+        }
+    );
+}
+
+#[test]
+fn test_header_invalid_sizes() {
+    let mut rom = cartridge_rom(2, 0x00, 0x00);
+    rom[0x148] = 0x09;
+    fix_checksums(&mut rom);
+    assert_eq!(
+        Header::parse(&rom).unwrap_err(),
+        HeaderError::InvalidRomSize(0x09)
+    );
+
+    let mut rom = cartridge_rom(2, 0x00, 0x06);
+    fix_checksums(&mut rom);
+    assert_eq!(
+        Header::parse(&rom).unwrap_err(),
+        HeaderError::InvalidRamSize(0x06)
+    );
+}
+
+#[test]
+fn test_header_global_checksum_mismatch_is_not_an_error() {
+    let mut rom = cartridge_rom(2, 0x00, 0x00);
+    fix_checksums(&mut rom);
+    rom[0x4000] ^= 0xFF;
+    let header = Header::parse(&rom).unwrap();
+    assert!(!header.global_checksum_valid);
+}
+
+#[test]
+fn test_header_summary() {
+    let rom = cartridge_rom(4, 0x03, 0x02);
+    let header = Header::parse(&rom).unwrap();
+    assert_eq!(
+        header.to_string(),
+        "\"TESTCART\", MBC1+RAM+BATTERY (0x03), ROM 64KB, RAM 8KB, licensee 0x00, Japan, version 0"
+    );
+}
+
+#[test]
+fn test_mmu_rejects_malformed_rom() {
+    let mut mmu = Mmu::new();
+    match mmu.load_cartridge_data(&[0u8; 0x100][..]) {
+        Err(GameboyError::InvalidHeader(HeaderError::TooShort(0x100))) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+    assert!(mmu.header().is_none());
+
+    let rom = cartridge_rom(2, 0x00, 0x00);
+    mmu.load_cartridge_data(&rom[..]).unwrap();
+    assert_eq!(mmu.header().unwrap().title, "TESTCART");
+}
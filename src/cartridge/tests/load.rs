@@ -0,0 +1,68 @@
+use cartridge::tests::*;
+use error::GameboyError;
+use mmu::Mmu;
+
+#[test]
+fn test_unsupported_cartridge_type() {
+    let rom = cartridge_rom(2, 0x22, 0x00);
+    let mut mmu = Mmu::new();
+    match mmu.load_cartridge_data(&rom[..]) {
+        Err(GameboyError::UnsupportedCartridge(0x22)) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_missing_files() {
+    let mut mmu = Mmu::new();
+    match mmu.load_cartridge("does/not/exist.gb") {
+        Err(GameboyError::Io(_)) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+    match Mmu::with_boot_rom("does/not/exist.bin") {
+        Err(GameboyError::Io(_)) => {}
+        Err(e) => panic!("Unexpected error {:?}", e),
+        Ok(_) => panic!("Boot ROM loaded"),
+    }
+}
+
+#[test]
+fn test_boot_rom_overlay() {
+    let mut boot = [0; 256];
+    boot[0] = 0x31;
+    boot[0xFF] = 0x50;
+    let mut mmu = Mmu::with_boot_rom_data(boot);
+    mmu.load_cartridge_data(&cartridge_rom(2, 0x00, 0x00)[..])
+        .unwrap();
+    assert!(!mmu.boot_rom_finished());
+    assert_eq!(mmu.read_u8(0x0000), 0x31);
+    assert_eq!(mmu.read_u8(0x00FF), 0x50);
+    assert_eq!(mmu.read_u8(0x0104), 0xCE);
+
+    mmu.write_u8(0xFF50, 0x01);
+    assert!(mmu.boot_rom_finished());
+    assert_eq!(mmu.read_u8(0x0000), 0x00);
+}
+
+#[test]
+fn test_rom_area_writes_are_not_stored() {
+    let rom = cartridge_rom(2, 0x00, 0x00);
+    let mut mmu = Mmu::new();
+    mmu.load_cartridge_data(&rom[..]).unwrap();
+
+    // Games write to the ROM area to program a controller,
+    // which is a no-op for cartridges without one
+    for addr in 0x0000..0x0100 {
+        mmu.write_u8(addr, 0xAA);
+    }
+    mmu.write_u8(0x2000, 0x01);
+    mmu.write_u8(0x7FFF, 0x01);
+    for addr in 0x0000..0x0100 {
+        assert_eq!(mmu.read_u8(addr), rom[addr as usize]);
+    }
+    assert_eq!(mmu.read_u8(0x4000), 1);
+
+    // No external RAM
+    mmu.write_u8(0xA000, 0x12);
+    assert_eq!(mmu.read_u8(0xA000), 0xFF);
+}
@@ -0,0 +1,126 @@
+use cartridge::tests::*;
+use mmu::Mmu;
+
+#[test]
+fn test_mbc1_initial_banks() {
+    let mbc = MBC1::new(rom(4), 0);
+    assert_eq!(bank_at(&mbc, 0x0000), 0);
+    assert_eq!(bank_at(&mbc, 0x4000), 1);
+}
+
+#[test]
+fn test_mbc1_rom_bank_select() {
+    let mut mbc = MBC1::new(rom(32), 0);
+    for bank in 1..32 {
+        mbc.write_u8(0x2000, bank as u8);
+        assert_eq!(bank_at(&mbc, 0x4000), bank);
+        assert_eq!(bank_at(&mbc, 0x0000), 0);
+    }
+    // Only the lower 5 bits are used
+    mbc.write_u8(0x3FFF, 0xE3);
+    assert_eq!(bank_at(&mbc, 0x4000), 3);
+}
+
+#[test]
+fn test_mbc1_bank_0_maps_to_bank_1() {
+    let mut mbc = MBC1::new(rom(128), 0);
+    mbc.write_u8(0x2000, 0);
+    assert_eq!(bank_at(&mbc, 0x4000), 1);
+
+    // The remap only looks at the lower 5 bits
+    for upper in 1..4 {
+        mbc.write_u8(0x4000, upper);
+        assert_eq!(bank_at(&mbc, 0x4000), (upper as usize) << 5 | 1);
+    }
+}
+
+#[test]
+fn test_mbc1_rom_bank_wraps_to_rom_size() {
+    let mut mbc = MBC1::new(rom(8), 0);
+    mbc.write_u8(0x2000, 0x0B);
+    assert_eq!(bank_at(&mbc, 0x4000), 3);
+}
+
+#[test]
+fn test_mbc1_upper_rom_bank_bits() {
+    let mut mbc = MBC1::new(rom(128), 0);
+    mbc.write_u8(0x2000, 0x05);
+    mbc.write_u8(0x4000, 0x02);
+    assert_eq!(bank_at(&mbc, 0x4000), 0x45);
+
+    // Simple mode keeps bank 0 at 0x0000-0x3FFF
+    assert_eq!(bank_at(&mbc, 0x0000), 0);
+
+    // Advanced mode maps bank 0x40 to 0x0000-0x3FFF
+    mbc.write_u8(0x6000, 0x01);
+    assert_eq!(bank_at(&mbc, 0x0000), 0x40);
+    assert_eq!(bank_at(&mbc, 0x4000), 0x45);
+
+    mbc.write_u8(0x6000, 0x00);
+    assert_eq!(bank_at(&mbc, 0x0000), 0);
+}
+
+#[test]
+fn test_mbc1_ram_enable() {
+    let mut mbc = MBC1::new(rom(2), 0x2000);
+    assert_eq!(mbc.read_u8(0xA000), 0xFF);
+    mbc.write_u8(0xA000, 0x12);
+    assert_eq!(mbc.read_u8(0xA000), 0xFF);
+
+    mbc.write_u8(0x0000, 0x0A);
+    mbc.write_u8(0xA000, 0x12);
+    mbc.write_u8(0xBFFF, 0x34);
+    assert_eq!(mbc.read_u8(0xA000), 0x12);
+    assert_eq!(mbc.read_u8(0xBFFF), 0x34);
+
+    // Any value without 0xA in the lower nibble disables the RAM
+    mbc.write_u8(0x1FFF, 0xA0);
+    assert_eq!(mbc.read_u8(0xA000), 0xFF);
+
+    mbc.write_u8(0x1000, 0xFA);
+    assert_eq!(mbc.read_u8(0xA000), 0x12);
+}
+
+#[test]
+fn test_mbc1_no_ram() {
+    let mut mbc = MBC1::new(rom(2), 0);
+    mbc.write_u8(0x0000, 0x0A);
+    mbc.write_u8(0xA000, 0x12);
+    assert_eq!(mbc.read_u8(0xA000), 0xFF);
+}
+
+#[test]
+fn test_mbc1_ram_banks() {
+    let mut mbc = MBC1::new(rom(4), 0x8000);
+    mbc.write_u8(0x0000, 0x0A);
+    mbc.write_u8(0x6000, 0x01);
+    for bank in 0..4 {
+        mbc.write_u8(0x4000, bank);
+        mbc.write_u8(0xA123, 0x10 + bank);
+    }
+    for bank in 0..4 {
+        mbc.write_u8(0x4000, bank);
+        assert_eq!(mbc.read_u8(0xA123), 0x10 + bank);
+    }
+
+    // Simple mode always uses RAM bank 0
+    mbc.write_u8(0x4000, 3);
+    mbc.write_u8(0x6000, 0x00);
+    assert_eq!(mbc.read_u8(0xA123), 0x10);
+}
+
+#[test]
+fn test_mbc1_through_mmu() {
+    let data = cartridge_rom(4, 0x03, 0x02);
+    let mut mmu = Mmu::new();
+    mmu.load_cartridge_data(&data[..]).unwrap();
+
+    assert_eq!(mmu.read_u8(0x4000), 1);
+    mmu.write_u8(0x2000, 2);
+    assert_eq!(mmu.read_u8(0x4000), 2);
+    assert_eq!(mmu.read_u8(0x0000), 0);
+
+    mmu.write_u8(0x0000, 0x0A);
+    mmu.write_u8(0xA000, 0x42);
+    assert_eq!(mmu.read_u8(0xA000), 0x42);
+}
@@ -0,0 +1,58 @@
+use cartridge::tests::*;
+use mmu::Mmu;
+
+#[test]
+fn test_mbc2_rom_bank_select() {
+    let mut mbc = MBC2::new(rom(16));
+    assert_eq!(bank_at(&mbc, 0x4000), 1);
+    for bank in 1..16 {
+        mbc.write_u8(0x2100, bank as u8);
+        assert_eq!(bank_at(&mbc, 0x4000), bank);
+        assert_eq!(bank_at(&mbc, 0x0000), 0);
+    }
+    mbc.write_u8(0x0100, 0);
+    assert_eq!(bank_at(&mbc, 0x4000), 1);
+
+    // Registers are selected with bit 8 of the address
+    mbc.write_u8(0x2000, 5);
+    assert_eq!(bank_at(&mbc, 0x4000), 1);
+    mbc.write_u8(0x3F00, 0xF5);
+    assert_eq!(bank_at(&mbc, 0x4000), 5);
+}
+
+#[test]
+fn test_mbc2_ram() {
+    let mut mbc = MBC2::new(rom(2));
+    mbc.write_u8(0xA000, 0x0C);
+    assert_eq!(mbc.read_u8(0xA000), 0xFF);
+
+    // RAM enable ignores writes with bit 8 of the address set
+    mbc.write_u8(0x0100, 0x0A);
+    assert_eq!(mbc.read_u8(0xA000), 0xFF);
+
+    mbc.write_u8(0x0000, 0x0A);
+    mbc.write_u8(0xA000, 0x3C);
+    mbc.write_u8(0xA1FF, 0x05);
+    // Only the lower 4 bits are stored, the upper bits read as 1
+    assert_eq!(mbc.read_u8(0xA000), 0xFC);
+    assert_eq!(mbc.read_u8(0xA1FF), 0xF5);
+    // 512 bytes are echoed through the whole RAM area
+    assert_eq!(mbc.read_u8(0xA200), 0xFC);
+    assert_eq!(mbc.read_u8(0xBFFF), 0xF5);
+
+    mbc.write_u8(0x0000, 0x00);
+    assert_eq!(mbc.read_u8(0xA000), 0xFF);
+}
+
+#[test]
+fn test_mbc2_through_mmu() {
+    let data = cartridge_rom(4, 0x06, 0x00);
+    let mut mmu = Mmu::new();
+    mmu.load_cartridge_data(&data[..]).unwrap();
+
+    mmu.write_u8(0x2100, 3);
+    assert_eq!(mmu.read_u8(0x4000), 3);
+    mmu.write_u8(0x0000, 0x0A);
+    mmu.write_u8(0xA010, 0x07);
+    assert_eq!(mmu.read_u8(0xA010), 0xF7);
+}
@@ -0,0 +1,53 @@
+use cartridge::tests::*;
+use mmu::Mmu;
+
+#[test]
+fn test_mbc3_rom_bank_select() {
+    let mut mbc = MBC3::new(rom(128), 0, None);
+    assert_eq!(bank_at(&mbc, 0x4000), 1);
+    for bank in 1..128 {
+        mbc.write_u8(0x2000, bank as u8);
+        assert_eq!(bank_at(&mbc, 0x4000), bank);
+        assert_eq!(bank_at(&mbc, 0x0000), 0);
+    }
+    mbc.write_u8(0x2000, 0);
+    assert_eq!(bank_at(&mbc, 0x4000), 1);
+
+    // Unlike MBC1, banks 0x20, 0x40 and 0x60 are reachable
+    mbc.write_u8(0x3FFF, 0x20);
+    assert_eq!(bank_at(&mbc, 0x4000), 0x20);
+}
+
+#[test]
+fn test_mbc3_ram_banks() {
+    let mut mbc = MBC3::new(rom(2), 0x8000, None);
+    mbc.write_u8(0xA000, 0x12);
+    assert_eq!(mbc.read_u8(0xA000), 0xFF);
+
+    mbc.write_u8(0x0000, 0x0A);
+    for bank in 0..4 {
+        mbc.write_u8(0x4000, bank);
+        mbc.write_u8(0xB000, 0x20 + bank);
+    }
+    for bank in 0..4 {
+        mbc.write_u8(0x4000, bank);
+        assert_eq!(mbc.read_u8(0xB000), 0x20 + bank);
+    }
+
+    mbc.write_u8(0x0000, 0x00);
+    assert_eq!(mbc.read_u8(0xB000), 0xFF);
+}
+
+#[test]
+fn test_mbc3_through_mmu() {
+    let data = cartridge_rom(8, 0x13, 0x03);
+    let mut mmu = Mmu::new();
+    mmu.load_cartridge_data(&data[..]).unwrap();
+
+    mmu.write_u8(0x2000, 7);
+    assert_eq!(mmu.read_u8(0x4000), 7);
+    mmu.write_u8(0x0000, 0x0A);
+    mmu.write_u8(0x4000, 2);
+    mmu.write_u8(0xA000, 0x99);
+    assert_eq!(mmu.read_u8(0xA000), 0x99);
+}
@@ -0,0 +1,67 @@
+use cartridge::tests::*;
+use mmu::Mmu;
+
+#[test]
+fn test_mbc5_rom_bank_select() {
+    let mut mbc = MBC5::new(rom(512), 0, false);
+    assert_eq!(bank_at(&mbc, 0x4000), 1);
+
+    // Bank 0 is not remapped
+    mbc.write_u8(0x2000, 0);
+    assert_eq!(bank_at(&mbc, 0x4000), 0);
+
+    mbc.write_u8(0x2000, 0xAB);
+    assert_eq!(bank_at(&mbc, 0x4000), 0xAB);
+    mbc.write_u8(0x3000, 0x01);
+    assert_eq!(bank_at(&mbc, 0x4000), 0x1AB);
+    mbc.write_u8(0x2FFF, 0x02);
+    assert_eq!(bank_at(&mbc, 0x4000), 0x102);
+    mbc.write_u8(0x3FFF, 0xFE);
+    assert_eq!(bank_at(&mbc, 0x4000), 0x02);
+    assert_eq!(bank_at(&mbc, 0x0000), 0);
+}
+
+#[test]
+fn test_mbc5_ram_banks() {
+    let mut mbc = MBC5::new(rom(2), 0x20000, false);
+    mbc.write_u8(0x0000, 0x0A);
+    for bank in 0..16 {
+        mbc.write_u8(0x4000, bank);
+        mbc.write_u8(0xA000, 0x30 + bank);
+    }
+    for bank in 0..16 {
+        mbc.write_u8(0x4000, bank);
+        assert_eq!(mbc.read_u8(0xA000), 0x30 + bank);
+    }
+    assert!(!mbc.rumble());
+}
+
+#[test]
+fn test_mbc5_rumble() {
+    let mut mbc = MBC5::new(rom(2), 0x8000, true);
+    mbc.write_u8(0x0000, 0x0A);
+    mbc.write_u8(0x4000, 0x01);
+    mbc.write_u8(0xA000, 0x11);
+    assert!(!mbc.rumble());
+
+    // Bit 3 enables the motor and is not part of the RAM bank number
+    mbc.write_u8(0x4000, 0x09);
+    assert!(mbc.rumble());
+    assert_eq!(mbc.read_u8(0xA000), 0x11);
+
+    mbc.write_u8(0x4000, 0x01);
+    assert!(!mbc.rumble());
+}
+
+#[test]
+fn test_mbc5_through_mmu() {
+    let data = cartridge_rom(4, 0x1B, 0x02);
+    let mut mmu = Mmu::new();
+    mmu.load_cartridge_data(&data[..]).unwrap();
+
+    mmu.write_u8(0x2000, 3);
+    assert_eq!(mmu.read_u8(0x4000), 3);
+    mmu.write_u8(0x0000, 0x0A);
+    mmu.write_u8(0xBFFF, 0x55);
+    assert_eq!(mmu.read_u8(0xBFFF), 0x55);
+}
@@ -0,0 +1,36 @@
+use cartridge::header::{header_checksum, NINTENDO_LOGO};
+use cartridge::*;
+
+/// Creates a ROM image where the first two bytes of every
+/// bank contain the bank number.
+pub fn rom(banks: usize) -> Vec<u8> {
+    let mut rom = vec![0; banks * ROM_BANK_SIZE];
+    for bank in 0..banks {
+        rom[bank * ROM_BANK_SIZE] = (bank & 0xFF) as u8;
+        rom[bank * ROM_BANK_SIZE + 1] = (bank >> 8) as u8;
+    }
+    rom
+}
+/// Creates a ROM image with a valid cartridge header
+pub fn cartridge_rom(banks: usize, cartridge_type: u8, ram_size: u8) -> Vec<u8> {
+    let mut rom = rom(banks);
+    rom[0x104..0x134].copy_from_slice(&NINTENDO_LOGO);
+    rom[0x134..0x13C].copy_from_slice(b"TESTCART");
+    rom[0x147] = cartridge_type;
+    rom[0x148] = (banks / 2).trailing_zeros() as u8;
+    rom[0x149] = ram_size;
+    rom[0x14D] = header_checksum(&rom);
+    rom
+}
+pub fn bank_at<C: Cartridge>(cartridge: &C, addr: u16) -> usize {
+    cartridge.read_u8(addr) as usize | (cartridge.read_u8(addr + 1) as usize) << 8
+}
+
+mod header;
+mod load;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod rtc;
+mod save;
@@ -0,0 +1,180 @@
+use cartridge::rtc::*;
+use cartridge::tests::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+const START: u64 = 1_500_000_000;
+
+struct TestClock(Rc<Cell<u64>>);
+impl Clock for TestClock {
+    fn now(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+fn rtc() -> (Rtc, Rc<Cell<u64>>) {
+    let time = Rc::new(Cell::new(START));
+    (Rtc::new(Box::new(TestClock(time.clone()))), time)
+}
+
+fn latch(rtc: &mut Rtc) -> RtcRegisters {
+    rtc.write_latch(0x00);
+    rtc.write_latch(0x01);
+    rtc.latched()
+}
+
+#[test]
+fn test_rtc_counts_time() {
+    let (mut rtc, time) = rtc();
+    time.set(START + 2 * 86400 + 3 * 3600 + 4 * 60 + 5);
+    let r = latch(&mut rtc);
+    assert_eq!(r.seconds, 5);
+    assert_eq!(r.minutes, 4);
+    assert_eq!(r.hours, 3);
+    assert_eq!(r.day_low, 2);
+    assert_eq!(r.day_high, 0);
+}
+
+#[test]
+fn test_rtc_latch_sequence() {
+    let (mut rtc, time) = rtc();
+    time.set(START + 10);
+    // Latched registers don't change until 0x00, 0x01 is written
+    assert_eq!(rtc.read(0x08), 0);
+    rtc.write_latch(0x01);
+    assert_eq!(rtc.read(0x08), 0);
+    rtc.write_latch(0x00);
+    rtc.write_latch(0x02);
+    rtc.write_latch(0x01);
+    assert_eq!(rtc.read(0x08), 0);
+    rtc.write_latch(0x00);
+    rtc.write_latch(0x01);
+    assert_eq!(rtc.read(0x08), 10);
+
+    time.set(START + 20);
+    assert_eq!(rtc.read(0x08), 10);
+    latch(&mut rtc);
+    assert_eq!(rtc.read(0x08), 20);
+}
+
+#[test]
+fn test_rtc_day_counter_carry() {
+    let (mut rtc, time) = rtc();
+    time.set(START + 300 * 86400);
+    let r = latch(&mut rtc);
+    assert_eq!(r.day_low, 44);
+    assert_eq!(r.day_high, 0x01);
+
+    time.set(START + 513 * 86400);
+    let r = latch(&mut rtc);
+    assert_eq!(r.day_low, 1);
+    assert_eq!(r.day_high, 0x80);
+
+    // The carry bit stays set until it is cleared by the game
+    time.set(START + 514 * 86400);
+    assert_eq!(latch(&mut rtc).day_high, 0x80);
+    rtc.write(0x0C, 0x00);
+    assert_eq!(latch(&mut rtc).day_high, 0x00);
+}
+
+#[test]
+fn test_rtc_halt() {
+    let (mut rtc, time) = rtc();
+    time.set(START + 5);
+    rtc.write(0x0C, 0x40);
+    time.set(START + 100);
+    let r = latch(&mut rtc);
+    assert_eq!(r.seconds, 5);
+    assert_eq!(r.day_high, 0x40);
+
+    rtc.write(0x0C, 0x00);
+    time.set(START + 110);
+    assert_eq!(latch(&mut rtc).seconds, 15);
+}
+
+#[test]
+fn test_rtc_write_registers() {
+    let (mut rtc, time) = rtc();
+    rtc.write(0x08, 58);
+    rtc.write(0x09, 59);
+    rtc.write(0x0A, 23);
+    rtc.write(0x0B, 0xFF);
+    rtc.write(0x0C, 0x01);
+    time.set(START + 2);
+    let r = latch(&mut rtc);
+    assert_eq!(r.seconds, 0);
+    assert_eq!(r.minutes, 0);
+    assert_eq!(r.hours, 0);
+    assert_eq!(r.day_low, 0);
+    assert_eq!(r.day_high, 0x80);
+}
+
+#[test]
+fn test_rtc_footer_layout() {
+    let (mut rtc, time) = rtc();
+    time.set(START + 3600 + 2);
+    latch(&mut rtc);
+    time.set(START + 3600 + 7);
+    let footer = rtc.footer();
+    assert_eq!(footer.len(), 48);
+    assert_eq!(
+        &footer[0..20],
+        &[7, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(
+        &footer[20..40],
+        &[2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(&footer[40..48], &(START + 3600 + 7).to_le_bytes());
+}
+
+#[test]
+fn test_rtc_footer_restore() {
+    let (mut rtc, time) = rtc();
+    time.set(START + 30);
+    latch(&mut rtc);
+    let footer = rtc.footer();
+
+    // The clock keeps running while the emulator is not
+    let (mut restored, restored_time) = self::rtc();
+    restored_time.set(START + 90);
+    restored.load_footer(&footer);
+    assert_eq!(restored.latched().seconds, 30);
+    assert_eq!(restored.registers().seconds, 30);
+    assert_eq!(restored.registers().minutes, 1);
+
+    // Footers of other sizes are ignored
+    restored.load_footer(&footer[0..44]);
+    assert_eq!(restored.registers().minutes, 1);
+}
+
+#[test]
+fn test_mbc3_rtc_registers() {
+    let (rtc, time) = rtc();
+    let mut mbc = MBC3::new(rom(2), 0x2000, Some(rtc));
+    mbc.write_u8(0x0000, 0x0A);
+    mbc.write_u8(0xA000, 0x12);
+
+    time.set(START + 61);
+    mbc.write_u8(0x6000, 0x00);
+    mbc.write_u8(0x6000, 0x01);
+    mbc.write_u8(0x4000, 0x08);
+    assert_eq!(mbc.read_u8(0xA000), 1);
+    mbc.write_u8(0x4000, 0x09);
+    assert_eq!(mbc.read_u8(0xBFFF), 1);
+
+    mbc.write_u8(0x4000, 0x0A);
+    mbc.write_u8(0xA000, 5);
+    mbc.write_u8(0x6000, 0x00);
+    mbc.write_u8(0x6000, 0x01);
+    assert_eq!(mbc.read_u8(0xA000), 5);
+
+    // RAM is still reachable through banks 0-3
+    mbc.write_u8(0x4000, 0x00);
+    assert_eq!(mbc.read_u8(0xA000), 0x12);
+
+    // RTC registers are disabled together with RAM
+    mbc.write_u8(0x4000, 0x0A);
+    mbc.write_u8(0x0000, 0x00);
+    assert_eq!(mbc.read_u8(0xA000), 0xFF);
+}
@@ -0,0 +1,87 @@
+use cartridge::rtc::*;
+use cartridge::tests::*;
+use mmu::Mmu;
+
+#[test]
+fn test_has_battery() {
+    // 0x11 (MBC3) and 0x12 (MBC3+RAM) have no battery
+    let battery = [0x03, 0x06, 0x0F, 0x10, 0x13, 0x1B, 0x1E];
+    for cartridge_type in 0..=0xFF {
+        assert_eq!(
+            has_battery(cartridge_type),
+            battery.contains(&cartridge_type),
+            "cartridge type {:02X}",
+            cartridge_type
+        );
+    }
+}
+
+#[test]
+fn test_save_data_roundtrip() {
+    let mut mbc = MBC1::new(rom(2), 0x8000);
+    mbc.write_u8(0x0000, 0x0A);
+    mbc.write_u8(0x6000, 0x01);
+    mbc.write_u8(0x4000, 0x02);
+    mbc.write_u8(0xA001, 0x77);
+    let data = mbc.save_data();
+    assert_eq!(data.len(), 0x8000);
+    assert_eq!(data[2 * 0x2000 + 1], 0x77);
+
+    let mut restored = MBC1::new(rom(2), 0x8000);
+    restored.load_save_data(&data);
+    restored.write_u8(0x0000, 0x0A);
+    restored.write_u8(0x6000, 0x01);
+    restored.write_u8(0x4000, 0x02);
+    assert_eq!(restored.read_u8(0xA001), 0x77);
+}
+
+#[test]
+fn test_save_data_short_file() {
+    let mut mbc = MBC5::new(rom(2), 0x2000, false);
+    mbc.load_save_data(&[1, 2, 3]);
+    assert_eq!(&mbc.ram()[0..4], &[1, 2, 3, 0]);
+}
+
+#[test]
+fn test_mbc3_save_data_with_rtc_footer() {
+    struct FixedClock;
+    impl Clock for FixedClock {
+        fn now(&self) -> u64 {
+            1000
+        }
+    }
+
+    let mut mbc = MBC3::new(rom(2), 0x2000, Some(Rtc::new(Box::new(FixedClock))));
+    mbc.write_u8(0x0000, 0x0A);
+    mbc.write_u8(0xA000, 0x42);
+    mbc.write_u8(0x4000, 0x09);
+    mbc.write_u8(0xA000, 30);
+    let data = mbc.save_data();
+    assert_eq!(data.len(), 0x2000 + RTC_FOOTER_SIZE);
+
+    let mut restored = MBC3::new(rom(2), 0x2000, Some(Rtc::new(Box::new(FixedClock))));
+    restored.load_save_data(&data);
+    assert_eq!(restored.ram()[0], 0x42);
+    assert_eq!(restored.rtc().unwrap().registers().minutes, 30);
+}
+
+#[test]
+fn test_mmu_save_data() {
+    let data = cartridge_rom(2, 0x01, 0x02);
+    let mut mmu = Mmu::new();
+    mmu.load_cartridge_data(&data[..]).unwrap();
+    assert!(!mmu.has_battery());
+    assert_eq!(mmu.save_data(), None);
+
+    let data = cartridge_rom(2, 0x03, 0x02);
+    let mut mmu = Mmu::new();
+    mmu.load_cartridge_data(&data[..]).unwrap();
+    assert!(mmu.has_battery());
+    mmu.load_save_data(&[0x12; 0x2000]);
+    mmu.write_u8(0x0000, 0x0A);
+    assert_eq!(mmu.read_u8(0xA000), 0x12);
+    mmu.write_u8(0xA000, 0x34);
+    let save = mmu.save_data().unwrap();
+    assert_eq!(save.len(), 0x2000);
+    assert_eq!(&save[0..2], &[0x34, 0x12]);
+}
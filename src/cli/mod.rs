@@ -0,0 +1,189 @@
+#[cfg(test)]
+mod tests;
+
+use gameboy::display::Palette;
+use gameboy::Model;
+use headless;
+use log::LevelFilter;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Largest --scale, the window size has to fit a u32
+const MAX_SCALE: u32 = 16;
+/// Range of --speed, the frame time has to fit a Duration
+const MIN_SPEED: f64 = 0.01;
+const MAX_SPEED: f64 = 100.0;
+
+pub const USAGE: &str = "\
+Usage: gameboy [OPTIONS] ROM
+
+Options:
+    --boot-rom FILE       Start from a 256 byte DMG boot ROM
+    --model dmg           Hardware to emulate, CGB is not supported yet
+    --scale N             Window size as a multiple of 160x144, up to 16 [default: 3]
+    --palette grey|green  Colors of the four shades [default: grey]
+    --log-level LEVEL     off, error, warn, info, debug or trace [default: info]
+    --save-dir DIR        Directory of battery saves [default: next to the ROM]
+    --speed X             Emulation speed from 0.01 to 100, 2 is twice as fast [default: 1]
+    --trace FILE          Write the CPU state before every instruction to FILE
+    --link-listen ADDR    Wait for a link cable connection on ADDR
+    --link-connect ADDR   Connect the link cable to ADDR
+    -h, --help            Print this message
+
+Headless mode:
+    --headless            Run without a window, print the serial output
+    --frames N            Stop after N frames
+    --cycles N            Stop after N clock cycles
+    --until-serial TEXT   Stop once TEXT has been sent over the serial port
+    --until-loop          Stop at an instruction that jumps to itself
+    --screenshot FILE     Write the final frame to a PNG file
+
+The headless mode exits with status 2 if --until-serial or --until-loop
+was given but --frames or --cycles ran out first.";
+
+#[derive(Debug, PartialEq)]
+pub enum Link {
+    Listen(String),
+    Connect(String),
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub rom: PathBuf,
+    pub boot_rom: Option<PathBuf>,
+    pub model: Model,
+    pub scale: u32,
+    pub palette: Palette,
+    pub log_level: LevelFilter,
+    pub save_dir: Option<PathBuf>,
+    pub speed: f64,
+    pub trace: Option<PathBuf>,
+    pub link: Option<Link>,
+    /// Options of the headless mode, None when a window is opened
+    pub headless: Option<headless::Options>,
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Run(Box<Args>),
+    Help,
+}
+
+fn value<I: Iterator<Item = String>>(args: &mut I, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("{} requires a value", flag))
+}
+
+fn parsed<T: FromStr, I: Iterator<Item = String>>(args: &mut I, flag: &str) -> Result<T, String> {
+    let value = value(args, flag)?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_model(name: &str) -> Result<Model, String> {
+    match name {
+        "dmg" => Ok(Model::Dmg),
+        "cgb" => Err(format!("{} is not supported yet", Model::Cgb)),
+        _ => Err(format!("unknown model {}, expected dmg", name)),
+    }
+}
+
+fn parse_palette(name: &str) -> Result<Palette, String> {
+    match name {
+        "grey" => Ok(Palette::GREY),
+        "green" => Ok(Palette::GREEN),
+        _ => Err(format!("unknown palette {}, expected grey or green", name)),
+    }
+}
+
+/// Parses the command line arguments without the program name
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut rom = None;
+    let mut boot_rom = None;
+    let mut model = Model::Dmg;
+    let mut scale = 3;
+    let mut palette = Palette::default();
+    let mut log_level = LevelFilter::Info;
+    let mut save_dir = None;
+    let mut speed: f64 = 1.0;
+    let mut trace = None;
+    let mut link = None;
+    let mut headless = false;
+    let mut options = headless::Options::default();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--boot-rom" => boot_rom = Some(PathBuf::from(value(&mut args, &arg)?)),
+            "--model" => model = parse_model(&value(&mut args, &arg)?)?,
+            "--scale" => scale = parsed(&mut args, &arg)?,
+            "--palette" => palette = parse_palette(&value(&mut args, &arg)?)?,
+            "--log-level" => log_level = parsed(&mut args, &arg)?,
+            "--save-dir" => save_dir = Some(PathBuf::from(value(&mut args, &arg)?)),
+            "--speed" => speed = parsed(&mut args, &arg)?,
+            "--trace" => trace = Some(PathBuf::from(value(&mut args, &arg)?)),
+            "--link-listen" => link = Some(Link::Listen(value(&mut args, &arg)?)),
+            "--link-connect" => link = Some(Link::Connect(value(&mut args, &arg)?)),
+            "--headless" => headless = true,
+            "--frames" => options.stop.frames = Some(parsed(&mut args, &arg)?),
+            "--cycles" => options.stop.cycles = Some(parsed(&mut args, &arg)?),
+            "--until-serial" => options.stop.until_serial = Some(value(&mut args, &arg)?),
+            "--until-loop" => options.stop.until_loop = true,
+            "--screenshot" => options.screenshot = Some(PathBuf::from(value(&mut args, &arg)?)),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if rom.is_some() => return Err(format!("unexpected argument {}", arg)),
+            _ => rom = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let rom = rom.ok_or("no ROM given")?;
+    if !(1..=MAX_SCALE).contains(&scale) {
+        return Err(format!("--scale must be between 1 and {}", MAX_SCALE));
+    }
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        return Err(format!(
+            "--speed must be between {} and {}",
+            MIN_SPEED, MAX_SPEED
+        ));
+    }
+    let stops = options.stop.frames.is_some()
+        || options.stop.cycles.is_some()
+        || options.stop.until_serial.is_some()
+        || options.stop.until_loop;
+    let headless = if headless {
+        if !stops {
+            return Err(
+                "--headless requires --frames, --cycles, --until-serial or --until-loop"
+                    .to_string(),
+            );
+        }
+        if link.is_some() {
+            return Err("the link cable can't be used with --headless".to_string());
+        }
+        options.palette = palette;
+        Some(options)
+    } else {
+        if stops || options.screenshot.is_some() {
+            return Err("--frames, --cycles, --until-serial, --until-loop and \
+                 --screenshot require --headless"
+                .to_string());
+        }
+        None
+    };
+
+    Ok(Command::Run(Box::new(Args {
+        rom,
+        boot_rom,
+        model,
+        scale,
+        palette,
+        log_level,
+        save_dir,
+        speed,
+        trace,
+        link,
+        headless,
+    })))
+}
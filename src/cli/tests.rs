@@ -0,0 +1,186 @@
+use super::*;
+
+fn parse_args(args: &[&str]) -> Result<Command, String> {
+    parse(args.iter().map(|arg| arg.to_string()))
+}
+
+fn run_args(args: &[&str]) -> Args {
+    match parse_args(args) {
+        Ok(Command::Run(args)) => *args,
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+fn error(args: &[&str]) -> String {
+    match parse_args(args) {
+        Err(e) => e,
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_defaults() {
+    let args = run_args(&["game.gb"]);
+    assert_eq!(args.rom, PathBuf::from("game.gb"));
+    assert_eq!(args.boot_rom, None);
+    assert_eq!(args.model, Model::Dmg);
+    assert_eq!(args.scale, 3);
+    assert_eq!(args.palette, Palette::GREY);
+    assert_eq!(args.log_level, LevelFilter::Info);
+    assert_eq!(args.save_dir, None);
+    assert_eq!(args.speed, 1.0);
+    assert_eq!(args.trace, None);
+    assert_eq!(args.link, None);
+    assert!(args.headless.is_none());
+}
+
+#[test]
+fn test_options() {
+    let args = run_args(&[
+        "--boot-rom",
+        "dmg_boot.bin",
+        "--model",
+        "dmg",
+        "--scale",
+        "5",
+        "--palette",
+        "green",
+        "game.gb",
+        "--log-level",
+        "debug",
+        "--save-dir",
+        "saves",
+        "--speed",
+        "2.5",
+        "--trace",
+        "trace.txt",
+        "--link-connect",
+        "127.0.0.1:5000",
+    ]);
+    assert_eq!(args.rom, PathBuf::from("game.gb"));
+    assert_eq!(args.boot_rom, Some(PathBuf::from("dmg_boot.bin")));
+    assert_eq!(args.model, Model::Dmg);
+    assert_eq!(args.scale, 5);
+    assert_eq!(args.palette, Palette::GREEN);
+    assert_eq!(args.log_level, LevelFilter::Debug);
+    assert_eq!(args.save_dir, Some(PathBuf::from("saves")));
+    assert_eq!(args.speed, 2.5);
+    assert_eq!(args.trace, Some(PathBuf::from("trace.txt")));
+    assert_eq!(args.link, Some(Link::Connect("127.0.0.1:5000".to_string())));
+}
+
+#[test]
+fn test_headless() {
+    let args = run_args(&[
+        "game.gb",
+        "--headless",
+        "--frames",
+        "60",
+        "--cycles",
+        "1000",
+        "--until-serial",
+        "Passed",
+        "--until-loop",
+        "--screenshot",
+        "out.png",
+        "--palette",
+        "green",
+    ]);
+    let options = args.headless.unwrap();
+    assert_eq!(options.stop.frames, Some(60));
+    assert_eq!(options.stop.cycles, Some(1000));
+    assert_eq!(options.stop.until_serial, Some("Passed".to_string()));
+    assert!(options.stop.until_loop);
+    assert_eq!(options.screenshot, Some(PathBuf::from("out.png")));
+    assert_eq!(options.palette, Palette::GREEN);
+}
+
+#[test]
+fn test_help() {
+    match parse_args(&["game.gb", "--help"]) {
+        Ok(Command::Help) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+    match parse_args(&["-h"]) {
+        Ok(Command::Help) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(error(&[]), "no ROM given");
+    assert_eq!(error(&["a.gb", "b.gb"]), "unexpected argument b.gb");
+    assert_eq!(error(&["a.gb", "--fast"]), "unknown option --fast");
+    assert_eq!(
+        error(&["a.gb", "--boot-rom"]),
+        "--boot-rom requires a value"
+    );
+    assert_eq!(
+        error(&["a.gb", "--scale", "big"]),
+        "invalid value for --scale: big"
+    );
+    assert_eq!(
+        error(&["a.gb", "--scale", "0"]),
+        "--scale must be between 1 and 16"
+    );
+    assert_eq!(
+        error(&["a.gb", "--scale", "30000000"]),
+        "--scale must be between 1 and 16"
+    );
+    assert_eq!(
+        error(&["a.gb", "--speed", "-1"]),
+        "--speed must be between 0.01 and 100"
+    );
+    assert_eq!(
+        error(&["a.gb", "--speed", "1e-300"]),
+        "--speed must be between 0.01 and 100"
+    );
+    assert_eq!(
+        error(&["a.gb", "--speed", "1000"]),
+        "--speed must be between 0.01 and 100"
+    );
+    assert_eq!(
+        error(&["a.gb", "--speed", "nan"]),
+        "--speed must be between 0.01 and 100"
+    );
+    assert_eq!(
+        error(&["a.gb", "--model", "gba"]),
+        "unknown model gba, expected dmg"
+    );
+    assert_eq!(
+        error(&["a.gb", "--model", "cgb"]),
+        "CGB is not supported yet"
+    );
+    assert_eq!(
+        error(&["a.gb", "--palette", "red"]),
+        "unknown palette red, expected grey or green"
+    );
+    assert_eq!(
+        error(&["a.gb", "--log-level", "loud"]),
+        "invalid value for --log-level: loud"
+    );
+}
+
+#[test]
+fn test_headless_errors() {
+    assert_eq!(
+        error(&["a.gb", "--headless"]),
+        "--headless requires --frames, --cycles, --until-serial or --until-loop"
+    );
+    assert_eq!(
+        error(&["a.gb", "--frames", "10"]),
+        "--frames, --cycles, --until-serial, --until-loop and --screenshot require --headless"
+    );
+    assert_eq!(
+        error(&[
+            "a.gb",
+            "--headless",
+            "--frames",
+            "1",
+            "--link-listen",
+            ":5000"
+        ]),
+        "the link cable can't be used with --headless"
+    );
+}
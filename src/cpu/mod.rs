@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests;
 
+use interrupt::{Interrupt, INTERRUPT_FLAG_REGISTER};
 use mmu::Mmu;
 use std::default::Default;
 use std::fmt;
@@ -26,15 +27,30 @@ impl Default for RunState {
 pub enum InterruptState {
     Disabled,
     Enabled,
-    WillDisable,
+    /// EI was executed, interrupts are enabled after the next instruction
     WillEnable,
 }
 impl Default for InterruptState {
     fn default() -> InterruptState {
-        InterruptState::Enabled
+        InterruptState::Disabled
     }
 }
 
+/// Snapshot of the CPU registers
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Registers {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
+}
+
 #[derive(Default, Debug)]
 pub struct Cpu {
     a: u8,
@@ -49,6 +65,9 @@ pub struct Cpu {
     pub sp: u16,
     run_state: RunState,
     interrupts: InterruptState,
+    /// HALT was executed with interrupts disabled while an interrupt was
+    /// pending, the next byte is read without incrementing PC
+    halt_bug: bool,
     pub cycles: usize,
     pub debug: bool,
     pub debug_counter: usize,
@@ -127,6 +146,20 @@ impl Cpu {
     pub fn new() -> Cpu {
         Cpu::default()
     }
+    pub fn registers(&self) -> Registers {
+        Registers {
+            a: self.a,
+            f: self.f.0,
+            b: self.b,
+            c: self.c,
+            d: self.d,
+            e: self.e,
+            h: self.h,
+            l: self.l,
+            sp: self.sp,
+            pc: self.pc,
+        }
+    }
     #[inline]
     pub fn reset(&mut self) {
         self.a = 0x01;
@@ -139,73 +172,98 @@ impl Cpu {
         self.l = 0x4D;
         self.sp = 0xFFFE;
         self.pc = 0x100;
-        self.interrupts = InterruptState::Enabled; // TODO: This is just a guess
-    }
-    pub fn step(&mut self, mmu: &mut Mmu) {
-        if self.run_state == RunState::Running {
-            let opcode = {
-                let opcode = self.next_byte(mmu) as usize;
-                if opcode == 0xCB {
-                    self.next_byte(mmu) as usize + 0x100
-                } else {
-                    opcode
+        self.interrupts = InterruptState::Disabled;
+        self.halt_bug = false;
+    }
+    /// Runs one instruction or interrupt dispatch,
+    /// returns the number of clock cycles it took
+    pub fn step(&mut self, mmu: &mut Mmu) -> usize {
+        let start_cycles = self.cycles;
+        if !self.service_interrupt(mmu) {
+            self.execute(mmu);
+        }
+        // Peripherals are driven by the same clock as the CPU
+        let cycles = self.cycles - start_cycles;
+        mmu.step(cycles);
+        cycles
+    }
+
+    /// Jumps to the handler of the highest priority pending interrupt.
+    /// Returns false if no interrupt was serviced.
+    fn service_interrupt(&mut self, mmu: &mut Mmu) -> bool {
+        let interrupt = match mmu.pending_interrupt() {
+            Some(interrupt) => interrupt,
+            None => return false,
+        };
+        // Any pending interrupt wakes up the CPU from HALT,
+        // even if it is not going to be serviced
+        if self.run_state == RunState::Halted {
+            self.run_state = RunState::Running;
+        }
+        if self.interrupts != InterruptState::Enabled {
+            return false;
+        }
+
+        trace!("Servicing {:?} interrupt", interrupt);
+        mmu.clear_interrupt(interrupt);
+        self.interrupts = InterruptState::Disabled;
+        self.run_state = RunState::Running;
+        let pc = if self.halt_bug {
+            // EI followed by HALT, the handler returns to the HALT instruction
+            self.halt_bug = false;
+            self.pc.wrapping_sub(1)
+        } else {
+            self.pc
+        };
+        self.push_u16(mmu, pc);
+        self.pc = interrupt.vector();
+        self.cycles += 20;
+        true
+    }
+
+    fn execute(&mut self, mmu: &mut Mmu) {
+        match self.run_state {
+            RunState::Running => {
+                let opcode = {
+                    let opcode = self.next_byte(mmu) as usize;
+                    if opcode == 0xCB {
+                        self.next_byte(mmu) as usize + 0x100
+                    } else {
+                        opcode
+                    }
+                };
+                let enable_interrupts = self.interrupts == InterruptState::WillEnable;
+
+                //info!("{}", opcodes::MNEMONICS[opcode]);
+                opcodes::OPCODES[opcode](self, mmu);
+
+                if enable_interrupts && self.interrupts == InterruptState::WillEnable {
+                    // EI takes effect after the instruction following it,
+                    // unless that instruction was DI
+                    self.interrupts = InterruptState::Enabled;
                 }
-            };
-            let old_interrupts_state = self.interrupts;
-            let new_interrupts_state = match self.interrupts {
-                InterruptState::WillEnable => InterruptState::Enabled,
-                InterruptState::WillDisable => InterruptState::Disabled,
-                state => state,
-            };
-
-            //info!("{}", opcodes::MNEMONICS[opcode]);
-            opcodes::OPCODES[opcode](self, mmu);
-
-            if self.interrupts == old_interrupts_state {
-                // The instruction did not modify interrupts flag,
-                // enable/disable if we were in WillEnable/WillDisable state
-                self.interrupts = new_interrupts_state;
             }
-        } else {
-            // FIXME: How many cycles should we proceed in time
-            // if the CPU is halted?
-            self.cycles += 4;
-            let intf = mmu.read_u8(0xFF0F);
-            if intf == 0 {
-                return;
+            RunState::Halted => {
+                self.cycles += 4;
+            }
+            RunState::Stopped => {
+                self.cycles += 4;
+                // A button press wakes up the CPU regardless of IE
+                if mmu.read_u8(INTERRUPT_FLAG_REGISTER) & Interrupt::Joypad.bit() != 0 {
+                    self.run_state = RunState::Running;
+                }
             }
-
-            mmu.write_u8(0xFF0F, 0);
-            let pc = self.pc;
-            self.push_u16(mmu, pc);
-            self.run_state = RunState::Running;
-
-            self.pc = if (intf & 0x1) != 0 {
-                // V-Blank
-                0x40
-            } else if (intf & 0x2) != 0 {
-                // LCDC Status
-                // Modes 0, 1, 2
-                // LYC = LY coincide (selectable)
-                0x48
-            } else if (intf & 0x4) != 0 {
-                // Timer Overflow
-                0x50
-            } else if (intf & 0x8) != 0 {
-                // Serial transfer
-                0x58 // When transfer is complete
-            } else if (intf & 0x10) != 0 {
-                0x60
-            } else {
-                unreachable!("Unknown value for 0xFF0F");
-            };
         }
     }
 
     #[inline]
     fn next_byte(&mut self, mmu: &mut Mmu) -> u8 {
         let ret = mmu.read_u8(self.pc);
-        self.pc = self.pc.wrapping_add(1);
+        if self.halt_bug {
+            self.halt_bug = false;
+        } else {
+            self.pc = self.pc.wrapping_add(1);
+        }
         ret
     }
 
@@ -496,7 +554,6 @@ impl Cpu {
     fn stop(&mut self, _mmu: &mut Mmu) {
         self.run_state = RunState::Stopped;
         self.cycles += 4;
-        // TODO: Wake up to a button press
     }
 
     #[inline]
@@ -587,10 +644,15 @@ impl Cpu {
         self.cycles += 4;
     }
 
-    fn halt(&mut self, _mmu: &mut Mmu) {
-        self.run_state = RunState::Halted;
+    fn halt(&mut self, mmu: &mut Mmu) {
         self.cycles += 4;
-        // TODO: Wake up to an interrupt
+        if self.interrupts != InterruptState::Enabled && mmu.pending_interrupt().is_some() {
+            // HALT bug: the CPU does not halt and fails to increment PC
+            // when reading the next byte
+            self.halt_bug = true;
+        } else {
+            self.run_state = RunState::Halted;
+        }
     }
 
     make_adc!(adc_a_b, b);
@@ -755,14 +817,16 @@ impl Cpu {
 
     #[inline]
     fn di(&mut self, _mmu: &mut Mmu) {
-        // Disable interrupts after executing the next instruction
-        self.interrupts = InterruptState::WillDisable;
+        // Unlike EI, DI takes effect immediately
+        self.interrupts = InterruptState::Disabled;
+        self.cycles += 4;
     }
 
     #[inline]
     fn ei(&mut self, _mmu: &mut Mmu) {
         // Enable interrupts after executing the next instruction
         self.interrupts = InterruptState::WillEnable;
+        self.cycles += 4;
     }
 
     make_pop!(pop_bc, b, c);
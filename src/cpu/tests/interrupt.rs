@@ -0,0 +1,234 @@
+use cpu;
+use cpu::tests::*;
+use interrupt::{INTERRUPT_ENABLE_REGISTER, INTERRUPT_FLAG_REGISTER};
+
+fn init_interrupts(program: &[u8], enabled: u8, requested: u8) -> (Cpu, Mmu) {
+    let (mut cpu, mut mmu) = init(None);
+    cpu.reset();
+    for (i, b) in program.iter().enumerate() {
+        mmu.write_u8(cpu.pc + i as u16, *b);
+    }
+    mmu.write_u8(INTERRUPT_ENABLE_REGISTER, enabled);
+    mmu.write_u8(INTERRUPT_FLAG_REGISTER, requested);
+    (cpu, mmu)
+}
+
+fn return_address(cpu: &Cpu, mmu: &Mmu) -> u16 {
+    (mmu.read_u8(cpu.sp + 2) as u16) << 8 | mmu.read_u8(cpu.sp + 1) as u16
+}
+
+#[test]
+fn test_interrupt_dispatch() {
+    let (mut cpu, mut mmu) = init_interrupts(&[0x00], 0x04, 0x04);
+    cpu.interrupts = cpu::InterruptState::Enabled;
+    let old_sp = cpu.sp;
+
+    test(&mut cpu, &mut mmu, 20, |cpu, mmu| {
+        cpu.step(mmu);
+    });
+    assert_eq!(cpu.pc, 0x50);
+    assert_eq!(cpu.sp, old_sp - 2);
+    assert_eq!(return_address(&cpu, &mmu), 0x100);
+    assert_eq!(cpu.interrupts, cpu::InterruptState::Disabled);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x1F, 0x00);
+}
+
+#[test]
+fn test_interrupt_vectors() {
+    for (bit, vector) in [
+        (0x01, 0x40),
+        (0x02, 0x48),
+        (0x04, 0x50),
+        (0x08, 0x58),
+        (0x10, 0x60),
+    ]
+    .iter()
+    {
+        let (mut cpu, mut mmu) = init_interrupts(&[0x00], 0x1F, *bit);
+        cpu.interrupts = cpu::InterruptState::Enabled;
+        cpu.step(&mut mmu);
+        assert_eq!(cpu.pc, *vector, "IF {:02X}", bit);
+    }
+}
+
+#[test]
+fn test_interrupt_priority() {
+    let (mut cpu, mut mmu) = init_interrupts(&[0x00], 0x1F, 0x1C);
+    cpu.interrupts = cpu::InterruptState::Enabled;
+
+    // Only the serviced bit is cleared
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x50);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x1F, 0x18);
+
+    cpu.interrupts = cpu::InterruptState::Enabled;
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x58);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x1F, 0x10);
+}
+
+#[test]
+fn test_interrupt_enable_mask() {
+    // VBlank is requested but only timer is enabled
+    let (mut cpu, mut mmu) = init_interrupts(&[0x00, 0x00], 0x04, 0x01);
+    cpu.interrupts = cpu::InterruptState::Enabled;
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x101);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x1F, 0x01);
+
+    // The lower priority timer interrupt is serviced
+    // because VBlank is not enabled
+    mmu.write_u8(INTERRUPT_FLAG_REGISTER, 0x05);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x50);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x1F, 0x01);
+}
+
+#[test]
+fn test_interrupts_disabled() {
+    let (mut cpu, mut mmu) = init_interrupts(&[0x00, 0x00], 0x1F, 0x1F);
+    assert_eq!(cpu.interrupts, cpu::InterruptState::Disabled);
+    cpu.step(&mut mmu);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x102);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x1F, 0x1F);
+}
+
+#[test]
+fn test_ei_delay() {
+    // EI, NOP, NOP
+    let (mut cpu, mut mmu) = init_interrupts(&[0xFB, 0x00, 0x00], 0x01, 0x01);
+
+    test(&mut cpu, &mut mmu, 4, |cpu, mmu| {
+        cpu.step(mmu);
+    });
+    assert_eq!(cpu.pc, 0x101);
+    assert_eq!(cpu.interrupts, cpu::InterruptState::WillEnable);
+
+    // The instruction after EI is executed before the interrupt
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x102);
+    assert_eq!(cpu.interrupts, cpu::InterruptState::Enabled);
+
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x40);
+    assert_eq!(return_address(&cpu, &mmu), 0x102);
+}
+
+#[test]
+fn test_ei_di() {
+    // EI, DI, NOP
+    let (mut cpu, mut mmu) = init_interrupts(&[0xFB, 0xF3, 0x00], 0x01, 0x01);
+    cpu.step(&mut mmu);
+    cpu.step(&mut mmu);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x103);
+    assert_eq!(cpu.interrupts, cpu::InterruptState::Disabled);
+}
+
+#[test]
+fn test_di_is_immediate() {
+    // DI, NOP
+    let (mut cpu, mut mmu) = init_interrupts(&[0xF3, 0x00], 0x01, 0x00);
+    cpu.interrupts = cpu::InterruptState::Enabled;
+    test(&mut cpu, &mut mmu, 4, |cpu, mmu| {
+        cpu.step(mmu);
+    });
+    mmu.write_u8(INTERRUPT_FLAG_REGISTER, 0x01);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x102);
+}
+
+#[test]
+fn test_reti_enables_immediately() {
+    // RETI, NOP
+    let (mut cpu, mut mmu) = init_interrupts(&[0xD9], 0x01, 0x01);
+    cpu.sp -= 2;
+    mmu.write_u8(cpu.sp + 1, 0x00);
+    mmu.write_u8(cpu.sp + 2, 0x02);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x200);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x40);
+    assert_eq!(return_address(&cpu, &mmu), 0x200);
+}
+
+#[test]
+fn test_halt_wakes_up_to_interrupt() {
+    // HALT, NOP
+    let (mut cpu, mut mmu) = init_interrupts(&[0x76, 0x00], 0x04, 0x00);
+    cpu.interrupts = cpu::InterruptState::Enabled;
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.run_state, cpu::RunState::Halted);
+
+    for _ in 0..10 {
+        test(&mut cpu, &mut mmu, 4, |cpu, mmu| {
+            cpu.step(mmu);
+        });
+    }
+    assert_eq!(cpu.run_state, cpu::RunState::Halted);
+    assert_eq!(cpu.pc, 0x101);
+
+    mmu.write_u8(INTERRUPT_FLAG_REGISTER, 0x04);
+    test(&mut cpu, &mut mmu, 20, |cpu, mmu| {
+        cpu.step(mmu);
+    });
+    assert_eq!(cpu.run_state, cpu::RunState::Running);
+    assert_eq!(cpu.pc, 0x50);
+    assert_eq!(return_address(&cpu, &mmu), 0x101);
+}
+
+#[test]
+fn test_halt_ignores_disabled_interrupts() {
+    let (mut cpu, mut mmu) = init_interrupts(&[0x76, 0x00], 0x04, 0x00);
+    cpu.step(&mut mmu);
+    mmu.write_u8(INTERRUPT_FLAG_REGISTER, 0x1B);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.run_state, cpu::RunState::Halted);
+}
+
+#[test]
+fn test_halt_wakes_up_without_ime() {
+    // HALT, NOP
+    let (mut cpu, mut mmu) = init_interrupts(&[0x76, 0x00], 0x04, 0x00);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.run_state, cpu::RunState::Halted);
+
+    // Execution continues after HALT without servicing the interrupt
+    mmu.write_u8(INTERRUPT_FLAG_REGISTER, 0x04);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.run_state, cpu::RunState::Running);
+    assert_eq!(cpu.pc, 0x102);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x1F, 0x04);
+}
+
+#[test]
+fn test_halt_bug() {
+    // HALT, INC A, NOP
+    let (mut cpu, mut mmu) = init_interrupts(&[0x76, 0x3C, 0x00], 0x04, 0x04);
+    cpu.a = 0;
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.run_state, cpu::RunState::Running);
+    assert_eq!(cpu.pc, 0x101);
+
+    // The byte after HALT is read twice
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x101);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x102);
+    assert_eq!(cpu.a, 2);
+}
+
+#[test]
+fn test_halt_bug_after_ei() {
+    // EI, HALT
+    let (mut cpu, mut mmu) = init_interrupts(&[0xFB, 0x76], 0x04, 0x04);
+    cpu.step(&mut mmu);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.run_state, cpu::RunState::Running);
+
+    // The interrupt handler returns to the HALT instruction
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.pc, 0x50);
+    assert_eq!(return_address(&cpu, &mmu), 0x101);
+}
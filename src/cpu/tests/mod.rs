@@ -44,6 +44,7 @@ mod cp;
 mod daa;
 mod dec;
 mod inc;
+mod interrupt;
 mod jp;
 mod jr;
 mod ld;
@@ -61,4 +62,5 @@ mod shift;
 mod stop;
 mod sub;
 mod swap;
+mod timer;
 mod xor;
@@ -1,5 +1,6 @@
 use cpu;
 use cpu::tests::*;
+use joypad::Button;
 
 #[test]
 fn test_stop() {
@@ -9,7 +10,13 @@ fn test_stop() {
     test(&mut cpu, &mut mmu, 4, opcode(0x10));
     assert_eq!(cpu.run_state, cpu::RunState::Stopped);
 
-    // TODO: Simulate button press and wake up the processor
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.run_state, cpu::RunState::Stopped);
+
+    mmu.write_u8(0xFF00, 0x10);
+    mmu.press_button(Button::Start);
+    cpu.step(&mut mmu);
+    assert_eq!(cpu.run_state, cpu::RunState::Running);
 }
 
 #[test]
@@ -19,6 +26,4 @@ fn test_halt() {
     assert_eq!(cpu.run_state, cpu::RunState::Running);
     test(&mut cpu, &mut mmu, 4, opcode(0x76));
     assert_eq!(cpu.run_state, cpu::RunState::Halted);
-
-    // TODO: Simulate an interrupt and wake up the processor
 }
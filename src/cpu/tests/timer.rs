@@ -0,0 +1,26 @@
+use cpu::tests::*;
+use interrupt::INTERRUPT_FLAG_REGISTER;
+use timer::*;
+
+#[test]
+fn test_timer_driven_by_cpu_cycles() {
+    let (mut cpu, mut mmu) = init(None);
+    cpu.reset();
+    mmu.write_u8(TAC_REGISTER, 0x05);
+    mmu.write_u8(TMA_REGISTER, 0x80);
+    mmu.write_u8(TIMA_REGISTER, 0xFE);
+    mmu.write_u8(INTERRUPT_FLAG_REGISTER, 0);
+
+    // JR -2 takes 12 cycles and loops forever,
+    // TIMA is incremented every 16 cycles
+    mmu.write_u8(cpu.pc, 0x18);
+    mmu.write_u8(cpu.pc + 1, -2i8 as u8);
+    cpu.step(&mut mmu);
+    cpu.step(&mut mmu);
+    assert_eq!(mmu.read_u8(TIMA_REGISTER), 0xFF);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x04, 0);
+    cpu.step(&mut mmu);
+    assert_eq!(mmu.read_u8(TIMA_REGISTER), 0x80);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x04, 0x04);
+    assert_eq!(mmu.read_u8(DIV_REGISTER), 0);
+}
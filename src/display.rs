@@ -1,14 +1,46 @@
+use joypad::ButtonEvent;
+
+/// RGB colors of the four shades, from the lightest to the darkest
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Palette(pub [[u8; 3]; 4]);
+impl Palette {
+    pub const GREY: Palette = Palette([[255, 255, 255], [192, 192, 192], [96, 96, 96], [0, 0, 0]]);
+    /// Colors of the original DMG screen
+    pub const GREEN: Palette = Palette([
+        [0x9B, 0xBC, 0x0F],
+        [0x8B, 0xAC, 0x0F],
+        [0x30, 0x62, 0x30],
+        [0x0F, 0x38, 0x0F],
+    ]);
+
+    pub fn rgb(&self, shade: u8) -> [u8; 3] {
+        self.0[shade as usize]
+    }
+}
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::GREY
+    }
+}
+
 pub trait Display {
-    fn write_scanline(&mut self, y: u8, data: &[u8]);
-    fn render_framebuffer(&mut self, scrollx: u8, scrolly: u8);
+    /// Shows a finished frame of `SCREEN_WIDTH` x `SCREEN_HEIGHT` pixels.
+    /// Each byte is a shade from 0 (lightest) to 3 (darkest),
+    /// the palette registers have already been applied.
+    fn render_frame(&mut self, frame: &[u8]);
+    /// False once the user has closed the display
+    fn is_running(&self) -> bool {
+        true
+    }
+    /// Buttons pressed or released since the previous call
+    fn button_events(&mut self) -> Vec<ButtonEvent> {
+        vec![]
+    }
 }
 
 pub struct DebugDisplay;
 impl Display for DebugDisplay {
-    fn write_scanline(&mut self, y: u8, _data: &[u8]) {
-        debug!("write_scanline, y: {}", y);
-    }
-    fn render_framebuffer(&mut self, scrollx: u8, scrolly: u8) {
-        debug!("render_framebuffer, x: {}, y: {}", scrollx, scrolly);
+    fn render_frame(&mut self, frame: &[u8]) {
+        debug!("render_frame, {} pixels", frame.len());
     }
 }
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests;
+
+use std::ops::Range;
+
+pub const DMA_REGISTER: u16 = 0xFF46;
+pub const OAM_START: u16 = 0xFE00;
+
+const TRANSFER_LENGTH: u16 = 0xA0;
+const CYCLES_PER_BYTE: usize = 4;
+
+/// OAM DMA transfer started by writing the source page to 0xFF46.
+///
+/// One byte is copied from XX00-XX9F to OAM every machine cycle,
+/// the whole transfer taking 640 clock cycles. The CPU can only
+/// access HRAM while the transfer is running.
+#[derive(Debug)]
+pub struct Dma {
+    source: u16,
+    cycles: usize,
+}
+
+impl Dma {
+    pub fn new(page: u8) -> Dma {
+        // The echo RAM range is read from the work RAM below it
+        let page = if page >= 0xE0 { page - 0x20 } else { page };
+        Dma {
+            source: (page as u16) << 8,
+            cycles: 0,
+        }
+    }
+
+    #[inline]
+    pub fn source(&self) -> u16 {
+        self.source
+    }
+
+    #[inline]
+    fn copied(&self) -> u16 {
+        (self.cycles / CYCLES_PER_BYTE).min(TRANSFER_LENGTH as usize) as u16
+    }
+
+    /// Advances the transfer by `cycles` clock cycles,
+    /// returns the offsets of the bytes to copy.
+    pub fn step(&mut self, cycles: usize) -> Range<u16> {
+        let start = self.copied();
+        self.cycles += cycles;
+        start..self.copied()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.copied() == TRANSFER_LENGTH
+    }
+}
@@ -0,0 +1,91 @@
+use cpu::Cpu;
+use dma::*;
+use mmu::Mmu;
+
+/// Mmu with 0xC000-0xC09F filled with an increasing pattern
+fn init() -> Mmu {
+    let mut mmu = Mmu::new();
+    for i in 0..0xA0 {
+        mmu.write_u8(0xC000 + i, i as u8 ^ 0x5A);
+    }
+    mmu
+}
+
+fn oam(mmu: &Mmu, offset: u16) -> u8 {
+    mmu.read_u8_direct(OAM_START + offset)
+}
+
+#[test]
+fn test_dma_copies_to_oam() {
+    let mut mmu = init();
+    mmu.write_u8(DMA_REGISTER, 0xC0);
+    assert!(mmu.dma_active());
+    mmu.step(640);
+    assert!(!mmu.dma_active());
+    for i in 0..0xA0 {
+        assert_eq!(mmu.read_u8(OAM_START + i), i as u8 ^ 0x5A);
+    }
+}
+
+#[test]
+fn test_dma_timing() {
+    let mut mmu = init();
+    mmu.write_u8(DMA_REGISTER, 0xC0);
+    mmu.step(4);
+    assert_eq!(oam(&mmu, 0), 0x5A);
+    assert_eq!(oam(&mmu, 1), 0x00);
+    mmu.step(4 * 100);
+    assert_eq!(oam(&mmu, 100), 100 ^ 0x5A);
+    assert_eq!(oam(&mmu, 101), 0x00);
+    mmu.step(4 * 58);
+    assert!(mmu.dma_active());
+    mmu.step(4);
+    assert!(!mmu.dma_active());
+    assert_eq!(oam(&mmu, 0x9F), 0x9F ^ 0x5A);
+}
+
+#[test]
+fn test_dma_from_echo_ram() {
+    let mut mmu = init();
+    mmu.write_u8(DMA_REGISTER, 0xE0);
+    mmu.step(640);
+    assert_eq!(oam(&mmu, 0x10), 0x10 ^ 0x5A);
+}
+
+#[test]
+fn test_cpu_only_accesses_hram_during_dma() {
+    let mut mmu = init();
+    mmu.write_u8(0xFF80, 0x12);
+    mmu.write_u8(DMA_REGISTER, 0xC0);
+    mmu.step(4);
+    assert_eq!(mmu.read_u8(0xC000), 0xFF);
+    assert_eq!(mmu.read_u8(0xFF80), 0x12);
+    mmu.write_u8(0xC001, 0x34);
+    mmu.write_u8(0xFF81, 0x56);
+    assert_eq!(mmu.read_u8(0xFF81), 0x56);
+
+    mmu.step(636);
+    assert_eq!(mmu.read_u8(0xC000), 0x5A);
+    assert_eq!(mmu.read_u8(0xC001), 0x01 ^ 0x5A);
+}
+
+#[test]
+fn test_dma_driven_by_cpu() {
+    // LD A,0xC0; LDH (0x46),A; JR -2 running from HRAM
+    let mut cpu = Cpu::new();
+    let mut mmu = init();
+    for (i, &byte) in [0x3E, 0xC0, 0xE0, 0x46, 0x18, 0xFE].iter().enumerate() {
+        mmu.write_u8(0xFF90 + i as u16, byte);
+    }
+    cpu.pc = 0xFF90;
+    cpu.step(&mut mmu);
+    cpu.step(&mut mmu);
+    assert!(mmu.dma_active());
+    let start = cpu.cycles();
+    while mmu.dma_active() {
+        cpu.step(&mut mmu);
+        assert_eq!(cpu.pc, 0xFF94);
+    }
+    assert!(cpu.cycles() - start <= 640);
+    assert_eq!(oam(&mmu, 0x9F), 0x9F ^ 0x5A);
+}
@@ -0,0 +1,52 @@
+use cartridge::HeaderError;
+use gameboy::Model;
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum GameboyError {
+    Io(io::Error),
+    InvalidHeader(HeaderError),
+    /// Cartridge type byte at 0x147 of a controller that is not emulated
+    UnsupportedCartridge(u8),
+    /// Boot ROM of the given size, the DMG boot ROM is 256 bytes
+    InvalidBootRom(usize),
+    UnsupportedModel(Model),
+}
+impl fmt::Display for GameboyError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameboyError::Io(e) => write!(fmt, "{}", e),
+            GameboyError::InvalidHeader(e) => write!(fmt, "invalid cartridge header: {}", e),
+            GameboyError::UnsupportedCartridge(ct) => {
+                write!(fmt, "cartridge type 0x{:02X} is not supported", ct)
+            }
+            GameboyError::InvalidBootRom(len) => {
+                write!(fmt, "boot ROM is {} bytes, expected 256", len)
+            }
+            GameboyError::UnsupportedModel(model) => {
+                write!(fmt, "{} is not supported", model)
+            }
+        }
+    }
+}
+impl error::Error for GameboyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            GameboyError::Io(e) => Some(e),
+            GameboyError::InvalidHeader(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for GameboyError {
+    fn from(e: io::Error) -> GameboyError {
+        GameboyError::Io(e)
+    }
+}
+impl From<HeaderError> for GameboyError {
+    fn from(e: HeaderError) -> GameboyError {
+        GameboyError::InvalidHeader(e)
+    }
+}
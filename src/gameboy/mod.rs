@@ -0,0 +1,297 @@
+#[cfg(test)]
+pub mod tests;
+
+use cartridge::Header;
+use cpu::Cpu;
+use display::Display;
+use error::GameboyError;
+use gpu::{Gpu, SCREEN_HEIGHT, SCREEN_WIDTH};
+use joypad::{Button, ButtonEvent};
+use mmu::Mmu;
+use serial::SerialLink;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Clock cycles per second
+pub const CLOCK_SPEED: usize = 4_194_304;
+/// 154 lines of 456 cycles
+pub const CYCLES_PER_FRAME: usize = 70_224;
+
+/// Hardware to emulate
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Model {
+    #[default]
+    Dmg,
+    /// Game Boy Color, not emulated yet
+    Cgb,
+}
+impl fmt::Display for Model {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Model::Dmg => write!(fmt, "DMG"),
+            Model::Cgb => write!(fmt, "CGB"),
+        }
+    }
+}
+
+enum Source {
+    File(PathBuf),
+    Data(Vec<u8>),
+}
+impl Source {
+    fn read(self) -> Result<Vec<u8>, GameboyError> {
+        match self {
+            Source::File(path) => Ok(fs::read(path)?),
+            Source::Data(data) => Ok(data),
+        }
+    }
+}
+
+/// Keeps the latest finished frame for the frontend
+struct Frame {
+    pixels: Box<[u8]>,
+    ready: bool,
+}
+impl Display for Frame {
+    fn render_frame(&mut self, frame: &[u8]) {
+        self.pixels.copy_from_slice(frame);
+        self.ready = true;
+    }
+}
+
+/// Configures a `GameBoy`.
+///
+/// Without a boot ROM the emulation starts from the state
+/// the boot ROM leaves the hardware in.
+#[derive(Default)]
+pub struct GameBoyBuilder {
+    rom: Option<Source>,
+    boot_rom: Option<Source>,
+    model: Model,
+    sample_rate: Option<u32>,
+    serial_link: Option<Box<dyn SerialLink>>,
+    trace: Option<Box<dyn Write>>,
+}
+impl GameBoyBuilder {
+    pub fn rom<P: AsRef<Path>>(mut self, path: P) -> GameBoyBuilder {
+        self.rom = Some(Source::File(path.as_ref().to_path_buf()));
+        self
+    }
+    pub fn rom_data(mut self, data: Vec<u8>) -> GameBoyBuilder {
+        self.rom = Some(Source::Data(data));
+        self
+    }
+    pub fn boot_rom<P: AsRef<Path>>(mut self, path: P) -> GameBoyBuilder {
+        self.boot_rom = Some(Source::File(path.as_ref().to_path_buf()));
+        self
+    }
+    pub fn boot_rom_data(mut self, data: Vec<u8>) -> GameBoyBuilder {
+        self.boot_rom = Some(Source::Data(data));
+        self
+    }
+    pub fn model(mut self, model: Model) -> GameBoyBuilder {
+        self.model = model;
+        self
+    }
+    /// Sample rate of the audio output in Hz
+    pub fn sample_rate(mut self, sample_rate: u32) -> GameBoyBuilder {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+    pub fn serial_link(mut self, link: Box<dyn SerialLink>) -> GameBoyBuilder {
+        self.serial_link = Some(link);
+        self
+    }
+    /// Writes the CPU state before every instruction to `out`
+    pub fn trace(mut self, out: Box<dyn Write>) -> GameBoyBuilder {
+        self.trace = Some(out);
+        self
+    }
+
+    pub fn build(self) -> Result<GameBoy, GameboyError> {
+        if self.model != Model::Dmg {
+            return Err(GameboyError::UnsupportedModel(self.model));
+        }
+        let mut cpu = Cpu::new();
+        let mut mmu = match self.boot_rom {
+            Some(source) => {
+                let data = source.read()?;
+                if data.len() != 256 {
+                    return Err(GameboyError::InvalidBootRom(data.len()));
+                }
+                let mut boot = [0; 256];
+                boot.copy_from_slice(&data);
+                Mmu::with_boot_rom_data(boot)
+            }
+            None => {
+                cpu.reset();
+                Mmu::new()
+            }
+        };
+        if let Some(source) = self.rom {
+            mmu.load_cartridge_data(&source.read()?[..])?;
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            mmu.apu_mut().set_sample_rate(sample_rate);
+        }
+        if let Some(link) = self.serial_link {
+            mmu.set_serial_link(link);
+        }
+        Ok(GameBoy {
+            cpu,
+            gpu: Gpu::new(),
+            mmu,
+            frame: Frame {
+                pixels: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT].into_boxed_slice(),
+                ready: false,
+            },
+            trace: self.trace,
+        })
+    }
+}
+
+/// The whole console, driven by the frontends
+pub struct GameBoy {
+    cpu: Cpu,
+    gpu: Gpu,
+    mmu: Mmu,
+    frame: Frame,
+    trace: Option<Box<dyn Write>>,
+}
+
+impl GameBoy {
+    pub fn builder() -> GameBoyBuilder {
+        GameBoyBuilder::default()
+    }
+
+    /// Runs one instruction or interrupt dispatch,
+    /// returns the number of clock cycles it took.
+    pub fn step_instruction(&mut self) -> usize {
+        if self.trace.is_some() {
+            self.write_trace();
+        }
+        let cycles = self.cpu.step(&mut self.mmu);
+        self.gpu.step(&mut self.frame, &mut self.mmu, cycles);
+        cycles
+    }
+
+    /// Writes the registers and the next four bytes from PC in the
+    /// format of Gameboy Doctor, so that traces can be compared
+    /// with other emulators
+    fn write_trace(&mut self) {
+        let r = self.cpu.registers();
+        let pc = |offset: u16| self.mmu.read_u8(r.pc.wrapping_add(offset));
+        let line = format!(
+            "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} \
+             SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}\n",
+            r.a,
+            r.f,
+            r.b,
+            r.c,
+            r.d,
+            r.e,
+            r.h,
+            r.l,
+            r.sp,
+            r.pc,
+            pc(0),
+            pc(1),
+            pc(2),
+            pc(3)
+        );
+        if let Some(ref mut out) = self.trace {
+            if let Err(e) = out.write_all(line.as_bytes()) {
+                warn!("Stopping the trace: {}", e);
+                self.trace = None;
+            }
+        }
+    }
+
+    /// Runs until the next frame is finished, or for the length of a frame
+    /// while the LCD is off. Returns the number of clock cycles run.
+    pub fn run_frame(&mut self) -> usize {
+        self.frame.ready = false;
+        let mut cycles = 0;
+        while !self.frame.ready && cycles < CYCLES_PER_FRAME {
+            cycles += self.step_instruction();
+        }
+        cycles
+    }
+
+    /// Runs whole instructions until at least `cycles` clock cycles
+    /// have passed, returns the number of clock cycles run.
+    pub fn run_cycles(&mut self, cycles: usize) -> usize {
+        let mut run = 0;
+        while run < cycles {
+            run += self.step_instruction();
+        }
+        run
+    }
+
+    /// Clock cycles since the start of the emulation
+    #[inline]
+    pub fn cycles(&self) -> usize {
+        self.cpu.cycles()
+    }
+
+    /// The latest finished frame of `SCREEN_WIDTH` x `SCREEN_HEIGHT`
+    /// shades from 0 (lightest) to 3 (darkest)
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.frame.pixels
+    }
+
+    pub fn press(&mut self, button: Button) {
+        self.mmu.press_button(button);
+    }
+
+    pub fn release(&mut self, button: Button) {
+        self.mmu.release_button(button);
+    }
+
+    pub fn handle_button_event(&mut self, event: ButtonEvent) {
+        match event {
+            ButtonEvent::Pressed(button) => self.press(button),
+            ButtonEvent::Released(button) => self.release(button),
+        }
+    }
+
+    /// Interleaved left and right samples produced since the previous call
+    pub fn audio_samples(&mut self) -> Vec<f32> {
+        self.mmu.apu_mut().take_samples()
+    }
+
+    pub fn set_serial_link(&mut self, link: Box<dyn SerialLink>) {
+        self.mmu.set_serial_link(link);
+    }
+
+    pub fn header(&self) -> Option<&Header> {
+        self.mmu.header()
+    }
+
+    pub fn has_battery(&self) -> bool {
+        self.mmu.has_battery()
+    }
+
+    /// Battery-backed cartridge memory, None if the cartridge has no battery
+    pub fn save_data(&mut self) -> Option<Vec<u8>> {
+        self.mmu.save_data()
+    }
+
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        self.mmu.load_save_data(data);
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn mmu(&self) -> &Mmu {
+        &self.mmu
+    }
+
+    pub fn mmu_mut(&mut self) -> &mut Mmu {
+        &mut self.mmu
+    }
+}
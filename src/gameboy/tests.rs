@@ -0,0 +1,153 @@
+use cartridge::tests::cartridge_rom;
+use error::GameboyError;
+use gameboy::*;
+use joypad::{Button, ButtonEvent};
+use runner::JR_SELF;
+use serial::{CaptureLink, SerialBuffer};
+
+/// ROM that jumps from the entry point to `program` at 0x150
+fn rom(program: &[u8]) -> Vec<u8> {
+    let mut rom = cartridge_rom(2, 0x00, 0x00);
+    // JP 0x0150
+    rom[0x100..0x103].copy_from_slice(&[0xC3, 0x50, 0x01]);
+    rom[0x150..0x150 + program.len()].copy_from_slice(program);
+    rom
+}
+
+/// Runs `program` from 0x150 of a ROM only cartridge
+pub fn gameboy(program: &[u8]) -> GameBoy {
+    GameBoy::builder().rom_data(rom(program)).build().unwrap()
+}
+
+#[test]
+fn test_step_instruction() {
+    let mut gameboy = gameboy(&JR_SELF);
+    assert_eq!(gameboy.cpu().pc, 0x100);
+    assert_eq!(gameboy.step_instruction(), 16);
+    assert_eq!(gameboy.cpu().pc, 0x150);
+    assert_eq!(gameboy.step_instruction(), 12);
+    assert_eq!(gameboy.cpu().pc, 0x150);
+    assert_eq!(gameboy.cycles(), 28);
+}
+
+#[test]
+fn test_run_cycles() {
+    let mut gameboy = gameboy(&JR_SELF);
+    let cycles = gameboy.run_cycles(1000);
+    assert!((1000..1012).contains(&cycles));
+    assert_eq!(gameboy.cycles(), cycles);
+}
+
+#[test]
+fn test_run_frame() {
+    let mut gameboy = gameboy(&JR_SELF);
+    // The first frame starts from line 0 and ends at line 144
+    let first = gameboy.run_frame();
+    assert!((144 * 456..144 * 456 + 12).contains(&first));
+    let second = gameboy.run_frame();
+    assert!((CYCLES_PER_FRAME - 12..CYCLES_PER_FRAME + 12).contains(&second));
+}
+
+#[test]
+fn test_run_frame_with_lcd_off() {
+    // LD A,0; LDH (40),A; JR -2
+    let mut gameboy = gameboy(&[0x3E, 0x00, 0xE0, 0x40, 0x18, 0xFE]);
+    let cycles = gameboy.run_frame();
+    assert!((CYCLES_PER_FRAME..CYCLES_PER_FRAME + 12).contains(&cycles));
+}
+
+#[test]
+fn test_framebuffer() {
+    let mut gameboy = gameboy(&JR_SELF);
+    assert!(gameboy.framebuffer().iter().all(|&shade| shade == 0));
+    // Every tile of the background is tile 0, make it color 3
+    for addr in 0x8000..0x8010 {
+        gameboy.mmu_mut().write_u8(addr, 0xFF);
+    }
+    gameboy.run_frame();
+    assert_eq!(gameboy.framebuffer().len(), 160 * 144);
+    assert!(gameboy.framebuffer().iter().all(|&shade| shade == 3));
+}
+
+#[test]
+fn test_input() {
+    let mut gameboy = gameboy(&JR_SELF);
+    gameboy.mmu_mut().write_u8(0xFF00, 0x10);
+    assert_eq!(gameboy.mmu().read_u8(0xFF00) & 0x0F, 0x0F);
+    gameboy.press(Button::Start);
+    assert_eq!(gameboy.mmu().read_u8(0xFF00) & 0x0F, 0x07);
+    gameboy.handle_button_event(ButtonEvent::Released(Button::Start));
+    assert_eq!(gameboy.mmu().read_u8(0xFF00) & 0x0F, 0x0F);
+}
+
+#[test]
+fn test_audio() {
+    let mut gameboy = gameboy(&JR_SELF);
+    gameboy.run_frame();
+    assert!(!gameboy.audio_samples().is_empty());
+    assert!(gameboy.audio_samples().is_empty());
+}
+
+#[test]
+fn test_serial_link() {
+    // LD A,'X'; LDH (01),A; LD A,81; LDH (02),A; JR -2
+    let program = [0x3E, b'X', 0xE0, 0x01, 0x3E, 0x81, 0xE0, 0x02, 0x18, 0xFE];
+    let output = SerialBuffer::new();
+    let mut gameboy = GameBoy::builder()
+        .rom_data(rom(&program))
+        .serial_link(Box::new(CaptureLink::new(output.clone())))
+        .build()
+        .unwrap();
+    gameboy.run_cycles(5000);
+    assert_eq!(output.take(), b"X");
+}
+
+#[test]
+fn test_boot_rom() {
+    let mut boot = vec![0; 256];
+    boot[..2].copy_from_slice(&JR_SELF);
+    let mut gameboy = GameBoy::builder()
+        .rom_data(cartridge_rom(2, 0x00, 0x00))
+        .boot_rom_data(boot)
+        .build()
+        .unwrap();
+    gameboy.run_cycles(100);
+    assert_eq!(gameboy.cpu().pc, 0x0000);
+    assert!(!gameboy.mmu().boot_rom_finished());
+}
+
+#[test]
+fn test_build_errors() {
+    match GameBoy::builder().boot_rom_data(vec![0; 10]).build() {
+        Err(GameboyError::InvalidBootRom(10)) => {}
+        Err(e) => panic!("Unexpected error {}", e),
+        Ok(_) => panic!("Boot ROM of 10 bytes was accepted"),
+    }
+    match GameBoy::builder().model(Model::Cgb).build() {
+        Err(GameboyError::UnsupportedModel(Model::Cgb)) => {}
+        Err(e) => panic!("Unexpected error {}", e),
+        Ok(_) => panic!("CGB was accepted"),
+    }
+    match GameBoy::builder().rom("does/not/exist.gb").build() {
+        Err(GameboyError::Io(_)) => {}
+        Err(e) => panic!("Unexpected error {}", e),
+        Ok(_) => panic!("Missing ROM was accepted"),
+    }
+}
+
+#[test]
+fn test_trace() {
+    let out = SerialBuffer::new();
+    let mut gameboy = GameBoy::builder()
+        .rom_data(rom(&JR_SELF))
+        .trace(Box::new(out.clone()))
+        .build()
+        .unwrap();
+    gameboy.step_instruction();
+    gameboy.step_instruction();
+    assert_eq!(
+        String::from_utf8(out.take()).unwrap(),
+        "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:C3,50,01,00\n\
+         A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0150 PCMEM:18,FE,00,00\n"
+    );
+}
@@ -1,49 +1,107 @@
 extern crate mini_gl_fb;
 
-use self::mini_gl_fb::MiniGlFb;
-use gameboy::display::Display;
+use self::mini_gl_fb::glutin::{ElementState, Event, GlContext, VirtualKeyCode, WindowEvent};
+use self::mini_gl_fb::GlutinBreakout;
+use gameboy::display::{Display, Palette};
+use gameboy::gpu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use gameboy::joypad::{Button, ButtonEvent};
+use std::mem;
+
+fn button(key: VirtualKeyCode) -> Option<Button> {
+    match key {
+        VirtualKeyCode::Right => Some(Button::Right),
+        VirtualKeyCode::Left => Some(Button::Left),
+        VirtualKeyCode::Up => Some(Button::Up),
+        VirtualKeyCode::Down => Some(Button::Down),
+        VirtualKeyCode::X => Some(Button::A),
+        VirtualKeyCode::Z => Some(Button::B),
+        VirtualKeyCode::Back => Some(Button::Select),
+        VirtualKeyCode::Return => Some(Button::Start),
+        _ => None,
+    }
+}
 
 pub struct GlDisplay {
-    buffer: Box<[u8]>,
-    output_buf: [u8; 160 * 144],
-    fb: MiniGlFb,
+    output_buf: Box<[u8]>,
+    palette: Palette,
+    window: GlutinBreakout,
+    running: bool,
+    button_events: Vec<ButtonEvent>,
 }
 impl GlDisplay {
-    pub fn new() -> GlDisplay {
-        let mut fb = mini_gl_fb::gotta_go_fast("GB", 160.0, 144.0);
-        fb.change_buffer_format::<u8>(mini_gl_fb::BufferFormat::R);
-        fb.use_grayscale_shader();
+    /// Opens a window of `scale` times the size of the screen
+    pub fn new(palette: Palette, scale: u32) -> GlDisplay {
+        let fb = mini_gl_fb::get_fancy(mini_gl_fb::Config {
+            window_title: "GB",
+            window_size: (
+                (SCREEN_WIDTH as u32 * scale) as f64,
+                (SCREEN_HEIGHT as u32 * scale) as f64,
+            ),
+            buffer_size: (SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32),
+            resizable: false,
+        });
         GlDisplay {
-            buffer: vec![255u8; 256 * 256].into_boxed_slice(),
-            fb,
-            output_buf: [255u8; 160 * 144],
+            window: fb.glutin_breakout(),
+            output_buf: vec![255u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4].into_boxed_slice(),
+            palette,
+            running: true,
+            button_events: vec![],
+        }
+    }
+
+    fn handle_events(&mut self) {
+        let running = &mut self.running;
+        let button_events = &mut self.button_events;
+        let mut resized = None;
+        self.window.events_loop.poll_events(|event| {
+            if let Event::WindowEvent { event, .. } = event {
+                match event {
+                    WindowEvent::CloseRequested => *running = false,
+                    WindowEvent::KeyboardInput { input, .. } => {
+                        let pressed = input.state == ElementState::Pressed;
+                        match input.virtual_keycode {
+                            Some(VirtualKeyCode::Escape) if !pressed => *running = false,
+                            Some(key) => {
+                                if let Some(button) = button(key) {
+                                    button_events.push(if pressed {
+                                        ButtonEvent::Pressed(button)
+                                    } else {
+                                        ButtonEvent::Released(button)
+                                    });
+                                }
+                            }
+                            None => {}
+                        }
+                    }
+                    WindowEvent::Resized(size) => resized = Some(size),
+                    _ => {}
+                }
+            }
+        });
+        if let Some(size) = resized {
+            let dpi_factor = self.window.gl_window.get_hidpi_factor();
+            let (width, height) = size.to_physical(dpi_factor).into();
+            self.window.gl_window.resize((width, height).into());
+            self.window.fb.resize_viewport(width, height);
         }
     }
 }
 impl Display for GlDisplay {
-    fn write_scanline(&mut self, y: u8, data: &[u8]) {
-        for (i, pixel) in data.into_iter().enumerate() {
-            // TODO: Palettes
-            let pixel = match pixel {
-                0 => 255u8,
-                1 => 192u8,
-                2 => 96u8,
-                3 => 0u8,
-                data => unreachable!("Invalid pixel data: {}", data),
-            };
-            self.buffer[256 * y as usize + i] = pixel;
+    fn render_frame(&mut self, frame: &[u8]) {
+        // The buffer starts from the bottom row
+        let rows = frame.chunks(SCREEN_WIDTH).rev();
+        let pixels = rows.flat_map(|row| row.iter());
+        for (rgba, &shade) in self.output_buf.chunks_mut(4).zip(pixels) {
+            rgba[..3].copy_from_slice(&self.palette.rgb(shade));
         }
+        self.window.fb.update_buffer(&self.output_buf);
+        self.window.gl_window.swap_buffers().unwrap();
+        self.handle_events();
     }
-    fn render_framebuffer(&mut self, scrollx: u8, scrolly: u8) {
-        let mut i = 0;
-        for y in (0u8..144).rev() {
-            for x in 0u8..160 {
-                let scrolledx = x.wrapping_add(scrollx);
-                let scrolledy = y.wrapping_add(scrolly);
-                self.output_buf[i] = self.buffer[scrolledy as usize * 256 + scrolledx as usize];
-                i += 1;
-            }
-        }
-        self.fb.update_buffer(&self.output_buf);
+    fn is_running(&self) -> bool {
+        self.running
+    }
+    fn button_events(&mut self) -> Vec<ButtonEvent> {
+        mem::take(&mut self.button_events)
     }
 }
@@ -0,0 +1,482 @@
+mod registers;
+#[cfg(test)]
+mod tests;
+
+pub use self::registers::{
+    LcdRegisters, BGP_REGISTER, LCDC_REGISTER, LYC_REGISTER, LY_REGISTER, OBP0_REGISTER,
+    OBP1_REGISTER, SCROLL_X_REGISTER, SCROLL_Y_REGISTER, STAT_REGISTER, WINDOW_X_REGISTER,
+    WINDOW_Y_REGISTER,
+};
+
+use display::Display;
+use dma::OAM_START;
+use interrupt::Interrupt;
+use mmu::Mmu;
+
+#[derive(Copy, Clone, Debug)]
+enum GpuState {
+    HBlank,
+    VBlank,
+    OAM,
+    OAMAndDisplayRam,
+}
+impl Into<u8> for GpuState {
+    fn into(self) -> u8 {
+        match self {
+            GpuState::HBlank => 0b0,
+            GpuState::VBlank => 0b01,
+            GpuState::OAM => 0b10,
+            GpuState::OAMAndDisplayRam => 0b11,
+        }
+    }
+}
+
+/// One dot is one clock cycle
+const DOTS_PER_LINE: usize = 456;
+const LINES_PER_FRAME: u8 = 154;
+const OAM_SEARCH_DOTS: usize = 80;
+/// Length of the pixel transfer without sprites, window or scrolling
+const PIXEL_TRANSFER_DOTS: usize = 172;
+const STAT_COINCIDENCE: u8 = 0x04;
+const STAT_HBLANK_INTERRUPT: u8 = 0x08;
+const STAT_VBLANK_INTERRUPT: u8 = 0x10;
+const STAT_OAM_INTERRUPT: u8 = 0x20;
+const STAT_LYC_INTERRUPT: u8 = 0x40;
+const SPRITE_COUNT: u16 = 40;
+const SPRITES_PER_LINE: usize = 10;
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+
+/// Decodes a row of a tile to 8 color indices, leftmost pixel first
+fn tile_row(mmu: &Mmu, addr: u16) -> [u8; 8] {
+    let low = mmu.read_u8_direct(addr);
+    let high = mmu.read_u8_direct(addr + 1);
+    let mut row = [0; 8];
+    for (i, pixel) in row.iter_mut().enumerate() {
+        let bit = 7 - i;
+        *pixel = ((high >> bit) & 0x01) << 1 | (low >> bit) & 0x01;
+    }
+    row
+}
+
+/// Decodes row `y` of the tile at column `x` of a background tile map
+fn tilemap_row(mmu: &Mmu, tilemap_start: u16, tile_data_start: u16, x: u8, y: u8) -> [u8; 8] {
+    let tile = mmu.read_u8_direct(tilemap_start + (y / 8) as u16 * 32 + (x / 8) as u16);
+    tile_row(mmu, tile_addr(tile_data_start, tile) + (y % 8) as u16 * 2)
+}
+
+/// Address of background or window tile `tile` in the tile data area
+fn tile_addr(tile_data_start: u16, tile: u8) -> u16 {
+    if tile_data_start == 0x8800 {
+        // Indexes are signed from -128 to 127 with tile 0 at 0x9000
+        0x9000u16.wrapping_add((tile as i8 as i16 * 16) as u16)
+    } else {
+        tile_data_start + tile as u16 * 16
+    }
+}
+
+/// Maps a color index to a shade through a BGP/OBP0/OBP1 palette
+#[inline]
+fn shade(palette: u8, color: u8) -> u8 {
+    (palette >> (color * 2)) & 0x03
+}
+
+/// OAM entry
+#[derive(Copy, Clone, Debug)]
+struct Sprite {
+    /// Screen coordinates of the top left corner
+    y: i16,
+    x: i16,
+    tile: u8,
+    attributes: u8,
+}
+impl Sprite {
+    fn read(mmu: &Mmu, index: u16) -> Sprite {
+        let addr = OAM_START + index * 4;
+        Sprite {
+            y: mmu.read_u8_direct(addr) as i16 - 16,
+            x: mmu.read_u8_direct(addr + 1) as i16 - 8,
+            tile: mmu.read_u8_direct(addr + 2),
+            attributes: mmu.read_u8_direct(addr + 3),
+        }
+    }
+    /// Background colors 1-3 are drawn over the sprite
+    fn behind_background(&self) -> bool {
+        self.attributes & 0x80 != 0
+    }
+    fn y_flip(&self) -> bool {
+        self.attributes & 0x40 != 0
+    }
+    fn x_flip(&self) -> bool {
+        self.attributes & 0x20 != 0
+    }
+    fn palette_register(&self) -> u16 {
+        if self.attributes & 0x10 == 0 {
+            OBP0_REGISTER
+        } else {
+            OBP1_REGISTER
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Gpu {
+    state: GpuState,
+    ly: u8,
+    /// Dot within the current line
+    dot: usize,
+    /// Dot where the pixel transfer of the current line ends
+    transfer_end: usize,
+    /// WY has matched LY during the current frame
+    window_y_reached: bool,
+    /// Line of the window to draw next, only advances
+    /// on lines where the window was drawn
+    window_line: u8,
+    /// Shades of the frame being drawn, one byte per pixel
+    framebuffer: Box<[u8]>,
+    /// State of the STAT interrupt line, the interrupt is
+    /// requested only when the line goes from low to high
+    stat_line: bool,
+}
+impl Gpu {
+    pub fn new() -> Gpu {
+        Gpu {
+            state: GpuState::OAM,
+            ly: 0,
+            dot: 0,
+            transfer_end: 0,
+            window_y_reached: false,
+            window_line: 0,
+            framebuffer: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT].into_boxed_slice(),
+            stat_line: false,
+        }
+    }
+
+    /// Advances the Gpu by `cycles` clock cycles
+    pub fn step<D: Display>(&mut self, display: &mut D, mmu: &mut Mmu, cycles: usize) {
+        let lcdc = LCDC(mmu.read_u8_direct(LCDC_REGISTER));
+        if let LCDCField::StopCompletely = lcdc.lcd_control_operation() {
+            // LY stays at 0 and the mode reads as HBlank while the LCD is off.
+            // The first line starts from the beginning when it is turned on.
+            self.state = GpuState::OAM;
+            self.ly = 0;
+            self.dot = 0;
+            self.stat_line = false;
+            mmu.lcd_mut().set_ly(0);
+            mmu.lcd_mut().set_stat_status(0);
+            return;
+        }
+
+        self.dot += cycles;
+        loop {
+            match self.state {
+                GpuState::OAM if self.dot >= OAM_SEARCH_DOTS => {
+                    self.state = GpuState::OAMAndDisplayRam;
+                    let ly = self.ly;
+                    self.transfer_end = OAM_SEARCH_DOTS + self.write_scanline(mmu, lcdc, ly);
+                }
+                GpuState::OAMAndDisplayRam if self.dot >= self.transfer_end => {
+                    self.state = GpuState::HBlank;
+                }
+                GpuState::HBlank | GpuState::VBlank if self.dot >= DOTS_PER_LINE => {
+                    self.dot -= DOTS_PER_LINE;
+                    self.ly = (self.ly + 1) % LINES_PER_FRAME;
+                    mmu.lcd_mut().set_ly(self.ly);
+                    if self.ly as usize == SCREEN_HEIGHT {
+                        self.state = GpuState::VBlank;
+                        mmu.request_interrupt(Interrupt::VBlank);
+                        display.render_frame(&self.framebuffer);
+                    } else if (self.ly as usize) < SCREEN_HEIGHT {
+                        self.state = GpuState::OAM;
+                    }
+                }
+                _ => break,
+            }
+            self.update_stat(mmu);
+        }
+        self.update_stat(mmu);
+    }
+
+    /// Updates the mode and LYC=LY bits of STAT and
+    /// requests the STAT interrupt if one of the enabled sources starts
+    fn update_stat(&mut self, mmu: &mut Mmu) {
+        let mut status: u8 = self.state.into();
+        if self.ly == mmu.read_u8_direct(LYC_REGISTER) {
+            status |= STAT_COINCIDENCE;
+        }
+        mmu.lcd_mut().set_stat_status(status);
+        let stat = mmu.read_u8_direct(STAT_REGISTER);
+
+        let source = match self.state {
+            GpuState::HBlank => STAT_HBLANK_INTERRUPT,
+            GpuState::VBlank => STAT_VBLANK_INTERRUPT,
+            GpuState::OAM => STAT_OAM_INTERRUPT,
+            GpuState::OAMAndDisplayRam => 0,
+        };
+        // All sources share one line, so a source becoming active while
+        // another one keeps the line high does not request an interrupt
+        let line = stat & source != 0
+            || stat & (STAT_LYC_INTERRUPT | STAT_COINCIDENCE)
+                == STAT_LYC_INTERRUPT | STAT_COINCIDENCE;
+        if line && !self.stat_line {
+            mmu.request_interrupt(Interrupt::LcdStat);
+        }
+        self.stat_line = line;
+    }
+
+    /// Draws line `ly` to the framebuffer with the scroll and window
+    /// registers as they are at this scanline. Returns the length of
+    /// the pixel transfer in dots.
+    fn write_scanline(&mut self, mmu: &Mmu, lcdc: LCDC, ly: u8) -> usize {
+        if ly as usize >= SCREEN_HEIGHT {
+            return PIXEL_TRANSFER_DOTS;
+        }
+        match lcdc.lcd_control_operation() {
+            LCDCField::Operation => {}
+            LCDCField::StopCompletely => {}
+            _ => unreachable!("lcdc control operation"),
+        }
+
+        let window_tilemap_display_start = match lcdc.window_tilemap_display() {
+            LCDCField::_9800_9BFF => 0x9800,
+            LCDCField::_9C00_9FFF => 0x9C00,
+            _ => unreachable!("tilemap display start"),
+        };
+
+        let tile_data_start = match lcdc.bg_and_window_tile_data_select() {
+            LCDCField::_8000_8FFF => 0x8000,
+            LCDCField::_8800_97FF => 0x8800,
+            _ => unreachable!("tile data start"),
+        };
+
+        let bg_tilemap_display_start = match lcdc.bg_tile_map_display_select() {
+            LCDCField::_9800_9BFF => 0x9800,
+            LCDCField::_9C00_9FFF => 0x9C00,
+            _ => unreachable!("bg tilemap display start"),
+        };
+
+        let scroll_x = mmu.read_u8_direct(SCROLL_X_REGISTER);
+        let y = ly.wrapping_add(mmu.read_u8_direct(SCROLL_Y_REGISTER));
+        let mut line_buf = [0u8; SCREEN_WIDTH];
+        if lcdc.bg_and_window_display() {
+            let mut pixels = [0; 8];
+            for (i, pixel) in line_buf.iter_mut().enumerate() {
+                let x = (i as u8).wrapping_add(scroll_x);
+                if i == 0 || x & 0x07 == 0 {
+                    pixels = tilemap_row(mmu, bg_tilemap_display_start, tile_data_start, x, y);
+                }
+                *pixel = pixels[x as usize % 8];
+            }
+        }
+
+        if ly == 0 {
+            self.window_y_reached = false;
+            self.window_line = 0;
+        }
+        if ly == mmu.read_u8_direct(WINDOW_Y_REGISTER) {
+            self.window_y_reached = true;
+        }
+        let window_x = mmu.read_u8_direct(WINDOW_X_REGISTER);
+        let window_drawn = lcdc.bg_and_window_display()
+            && lcdc.window_display()
+            && self.window_y_reached
+            && window_x <= 166;
+        if window_drawn {
+            // The window starts at WX - 7. With WX below 7 the
+            // window is shifted left instead of starting offscreen.
+            let start = window_x as i16 - 7;
+            let mut pixels = [0; 8];
+            for x in start.max(0)..SCREEN_WIDTH as i16 {
+                let window_x = (x - start) as u8;
+                if x == start.max(0) || window_x & 0x07 == 0 {
+                    pixels = tilemap_row(
+                        mmu,
+                        window_tilemap_display_start,
+                        tile_data_start,
+                        window_x,
+                        self.window_line,
+                    );
+                }
+                line_buf[x as usize] = pixels[window_x as usize % 8];
+            }
+            self.window_line += 1;
+        }
+
+        // Sprite priority depends on the background color index,
+        // the display gets the shades selected by the palettes
+        let bgp = mmu.read_u8_direct(BGP_REGISTER);
+        let start = ly as usize * SCREEN_WIDTH;
+        let shades = &mut self.framebuffer[start..start + SCREEN_WIDTH];
+        for (shade_buf, &color) in shades.iter_mut().zip(line_buf.iter()) {
+            *shade_buf = if lcdc.bg_and_window_display() {
+                shade(bgp, color)
+            } else {
+                0
+            };
+        }
+        let sprites = if lcdc.sprite_display() {
+            line_sprites(mmu, &lcdc, ly)
+        } else {
+            vec![]
+        };
+        draw_sprites(mmu, &lcdc, ly, &sprites, &line_buf, shades);
+
+        // Discarding the scrolled pixels of the first tile,
+        // fetching the window and the sprites stall the transfer
+        let mut dots = PIXEL_TRANSFER_DOTS + (scroll_x & 0x07) as usize;
+        if window_drawn {
+            dots += 6;
+        }
+        dots + sprite_penalty(&sprites, scroll_x)
+    }
+}
+
+fn sprite_height(lcdc: &LCDC) -> i16 {
+    match lcdc.sprite_size() {
+        LCDCField::_8x16 => 16,
+        _ => 8,
+    }
+}
+
+/// The first 10 sprites in OAM order on line `ly`
+fn line_sprites(mmu: &Mmu, lcdc: &LCDC, ly: u8) -> Vec<Sprite> {
+    let height = sprite_height(lcdc);
+    (0..SPRITE_COUNT)
+        .map(|index| Sprite::read(mmu, index))
+        .filter(|sprite| sprite.y <= ly as i16 && (ly as i16) < sprite.y + height)
+        .take(SPRITES_PER_LINE)
+        .collect()
+}
+
+/// Dots the pixel transfer is stalled by fetching `sprites`.
+///
+/// Each sprite takes 6 dots. The background fetch of the tile under the
+/// leftmost pixel of the sprite has to be finished first, which takes
+/// up to 5 more dots the first time a sprite is found on that tile.
+fn sprite_penalty(sprites: &[Sprite], scroll_x: u8) -> usize {
+    let mut tiles_waited = vec![];
+    let mut penalty = 0;
+    for sprite in sprites {
+        let x = sprite.x + (scroll_x & 0x07) as i16;
+        let tile = x.div_euclid(8);
+        if !tiles_waited.contains(&tile) {
+            tiles_waited.push(tile);
+            let pixels_right = 7 - x.rem_euclid(8) as usize;
+            penalty += pixels_right.saturating_sub(2);
+        }
+        penalty += 6;
+    }
+    penalty
+}
+
+/// Draws the sprites of line `ly` over the background.
+/// `line_buf` has the background color indices of the line.
+fn draw_sprites(
+    mmu: &Mmu,
+    lcdc: &LCDC,
+    ly: u8,
+    sprites: &[Sprite],
+    line_buf: &[u8],
+    shades: &mut [u8],
+) {
+    let height = sprite_height(lcdc);
+    // The sprite with the smaller X coordinate has priority, the sort
+    // is stable so OAM order decides between equal coordinates
+    let mut sprites = sprites.to_vec();
+    sprites.sort_by_key(|sprite| sprite.x);
+
+    let mut drawn = [false; SCREEN_WIDTH];
+    for sprite in sprites {
+        let mut row = ly as i16 - sprite.y;
+        if sprite.y_flip() {
+            row = height - 1 - row;
+        }
+        let tile = if height == 16 {
+            sprite.tile & 0xFE
+        } else {
+            sprite.tile
+        };
+        let pixels = tile_row(mmu, 0x8000 + tile as u16 * 16 + row as u16 * 2);
+        let palette = mmu.read_u8_direct(sprite.palette_register());
+        for i in 0..8 {
+            let x = sprite.x + i;
+            if x < 0 || x >= SCREEN_WIDTH as i16 || drawn[x as usize] {
+                continue;
+            }
+            let color = if sprite.x_flip() {
+                pixels[7 - i as usize]
+            } else {
+                pixels[i as usize]
+            };
+            // Color 0 is transparent and shows the sprites below
+            if color == 0 {
+                continue;
+            }
+            let x = x as usize;
+            drawn[x] = true;
+            if sprite.behind_background() && line_buf[x] != 0 {
+                continue;
+            }
+            shades[x] = shade(palette, color);
+        }
+    }
+}
+
+pub enum LCDCField {
+    Operation,
+    StopCompletely,
+    _8000_8FFF,
+    _8800_97FF,
+    _8x16,
+    _8x8,
+    _9800_9BFF,
+    _9C00_9FFF,
+}
+#[derive(Copy, Clone)]
+pub struct LCDC(pub u8);
+impl LCDC {
+    fn lcd_control_operation(&self) -> LCDCField {
+        if self.0 & 0x80 == 0 {
+            LCDCField::StopCompletely
+        } else {
+            LCDCField::Operation
+        }
+    }
+    fn window_tilemap_display(&self) -> LCDCField {
+        if self.0 & 0x40 == 0 {
+            LCDCField::_9800_9BFF
+        } else {
+            LCDCField::_9C00_9FFF
+        }
+    }
+    fn window_display(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+    fn bg_and_window_tile_data_select(&self) -> LCDCField {
+        if self.0 & 0x10 == 0 {
+            LCDCField::_8800_97FF
+        } else {
+            LCDCField::_8000_8FFF
+        }
+    }
+    fn bg_tile_map_display_select(&self) -> LCDCField {
+        if self.0 & 0x08 == 0 {
+            LCDCField::_9800_9BFF
+        } else {
+            LCDCField::_9C00_9FFF
+        }
+    }
+    fn sprite_size(&self) -> LCDCField {
+        if self.0 & 0x04 == 0 {
+            LCDCField::_8x8
+        } else {
+            LCDCField::_8x16
+        }
+    }
+    fn sprite_display(&self) -> bool {
+        self.0 & 0x02 != 0
+    }
+    fn bg_and_window_display(&self) -> bool {
+        self.0 & 0x01 != 0
+    }
+}
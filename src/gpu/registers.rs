@@ -0,0 +1,87 @@
+pub const LCDC_REGISTER: u16 = 0xFF40;
+pub const STAT_REGISTER: u16 = 0xFF41;
+pub const SCROLL_Y_REGISTER: u16 = 0xFF42;
+pub const SCROLL_X_REGISTER: u16 = 0xFF43;
+pub const LY_REGISTER: u16 = 0xFF44;
+pub const LYC_REGISTER: u16 = 0xFF45;
+pub const BGP_REGISTER: u16 = 0xFF47;
+pub const OBP0_REGISTER: u16 = 0xFF48;
+pub const OBP1_REGISTER: u16 = 0xFF49;
+pub const WINDOW_Y_REGISTER: u16 = 0xFF4A;
+pub const WINDOW_X_REGISTER: u16 = 0xFF4B;
+
+/// Bits of STAT that are updated by the Gpu
+const STAT_READ_ONLY: u8 = 0x07;
+
+/// LCD registers at 0xFF40-0xFF4B, except for the OAM DMA register.
+///
+/// The Mmu owns the registers so that the CPU can access them,
+/// LY and the mode and coincidence bits of STAT are read-only
+/// for the CPU and are updated by the Gpu.
+#[derive(Debug, Default)]
+pub struct LcdRegisters {
+    lcdc: u8,
+    stat: u8,
+    scy: u8,
+    scx: u8,
+    ly: u8,
+    lyc: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+    wy: u8,
+    wx: u8,
+}
+
+impl LcdRegisters {
+    pub fn new() -> LcdRegisters {
+        LcdRegisters::default()
+    }
+
+    /// Sets LY, which is read-only for the CPU
+    pub fn set_ly(&mut self, ly: u8) {
+        self.ly = ly;
+    }
+
+    /// Sets the read-only mode and coincidence bits of STAT
+    pub fn set_stat_status(&mut self, status: u8) {
+        self.stat = (self.stat & !STAT_READ_ONLY) | (status & STAT_READ_ONLY);
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            LCDC_REGISTER => self.lcdc,
+            // Bit 7 is unused and always set
+            STAT_REGISTER => self.stat | 0x80,
+            SCROLL_Y_REGISTER => self.scy,
+            SCROLL_X_REGISTER => self.scx,
+            LY_REGISTER => self.ly,
+            LYC_REGISTER => self.lyc,
+            BGP_REGISTER => self.bgp,
+            OBP0_REGISTER => self.obp0,
+            OBP1_REGISTER => self.obp1,
+            WINDOW_Y_REGISTER => self.wy,
+            WINDOW_X_REGISTER => self.wx,
+            _ => unreachable!("LCD register read from {:04X}", addr),
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            LCDC_REGISTER => self.lcdc = value,
+            STAT_REGISTER => {
+                self.stat = (self.stat & STAT_READ_ONLY) | (value & 0x78);
+            }
+            SCROLL_Y_REGISTER => self.scy = value,
+            SCROLL_X_REGISTER => self.scx = value,
+            LY_REGISTER => {}
+            LYC_REGISTER => self.lyc = value,
+            BGP_REGISTER => self.bgp = value,
+            OBP0_REGISTER => self.obp0 = value,
+            OBP1_REGISTER => self.obp1 = value,
+            WINDOW_Y_REGISTER => self.wy = value,
+            WINDOW_X_REGISTER => self.wx = value,
+            _ => unreachable!("LCD register write to {:04X}", addr),
+        }
+    }
+}
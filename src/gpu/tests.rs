@@ -0,0 +1,737 @@
+use display::Display;
+use gpu::*;
+use interrupt::{Interrupt, INTERRUPT_FLAG_REGISTER};
+use mmu::Mmu;
+
+const LCDC_DEFAULT: u8 = 0x93;
+
+fn init(lcdc: u8) -> (Gpu, Mmu) {
+    let mut mmu = Mmu::new();
+    mmu.write_u8(LCDC_REGISTER, lcdc);
+    mmu.write_u8(BGP_REGISTER, 0xE4);
+    mmu.write_u8(OBP0_REGISTER, 0xE4);
+    mmu.write_u8(OBP1_REGISTER, 0x1B);
+    // Tiles 1-3 are filled with colors 1-3
+    for color in 1..4 {
+        write_tile(&mut mmu, 0x8000 + color as u16 * 16, |_, _| color);
+    }
+    (Gpu::new(), mmu)
+}
+
+fn write_tile<F: Fn(u8, u8) -> u8>(mmu: &mut Mmu, addr: u16, pixel: F) {
+    for y in 0..8 {
+        let (mut low, mut high) = (0, 0);
+        for x in 0..8 {
+            let color = pixel(x, y);
+            low |= (color & 0x01) << (7 - x);
+            high |= (color >> 1) << (7 - x);
+        }
+        mmu.write_u8(addr + y as u16 * 2, low);
+        mmu.write_u8(addr + y as u16 * 2 + 1, high);
+    }
+}
+
+fn sprite(mmu: &mut Mmu, index: u16, x: i16, y: i16, tile: u8, attributes: u8) {
+    let addr = OAM_START + index * 4;
+    mmu.write_u8(addr, (y + 16) as u8);
+    mmu.write_u8(addr + 1, (x + 8) as u8);
+    mmu.write_u8(addr + 2, tile);
+    mmu.write_u8(addr + 3, attributes);
+}
+
+/// Renders line `ly` and returns its 160 pixels
+fn render_line(gpu: &mut Gpu, mmu: &Mmu, ly: u8) -> Vec<u8> {
+    gpu.write_scanline(mmu, LCDC(mmu.read_u8(LCDC_REGISTER)), ly);
+    let start = ly as usize * SCREEN_WIDTH;
+    gpu.framebuffer[start..start + SCREEN_WIDTH].to_vec()
+}
+
+/// Line with `color` at pixels `start..end`
+fn line(segments: &[(usize, usize, u8)]) -> Vec<u8> {
+    let mut line = vec![0; 160];
+    for &(start, end, color) in segments {
+        for pixel in &mut line[start..end] {
+            *pixel = color;
+        }
+    }
+    line
+}
+
+#[test]
+fn test_sprite_position() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    sprite(&mut mmu, 0, 10, 20, 1, 0);
+    assert_eq!(render_line(&mut gpu, &mmu, 19), line(&[]));
+    assert_eq!(render_line(&mut gpu, &mmu, 20), line(&[(10, 18, 1)]));
+    assert_eq!(render_line(&mut gpu, &mmu, 27), line(&[(10, 18, 1)]));
+    assert_eq!(render_line(&mut gpu, &mmu, 28), line(&[]));
+}
+
+#[test]
+fn test_sprite_partially_offscreen() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    sprite(&mut mmu, 0, -5, 0, 1, 0);
+    sprite(&mut mmu, 1, 155, 0, 2, 0);
+    sprite(&mut mmu, 2, 40, -4, 3, 0);
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[(0, 3, 1), (40, 48, 3), (155, 160, 2)])
+    );
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 3),
+        line(&[(0, 3, 1), (40, 48, 3), (155, 160, 2)])
+    );
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 4),
+        line(&[(0, 3, 1), (155, 160, 2)])
+    );
+}
+
+#[test]
+fn test_sprite_flip() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    // Color 3 in the top left corner, color 1 elsewhere
+    write_tile(
+        &mut mmu,
+        0x8040,
+        |x, y| if x == 0 && y == 0 { 3 } else { 1 },
+    );
+    sprite(&mut mmu, 0, 0, 0, 4, 0x00);
+    sprite(&mut mmu, 1, 8, 0, 4, 0x20);
+    sprite(&mut mmu, 2, 16, 0, 4, 0x40);
+    sprite(&mut mmu, 3, 24, 0, 4, 0x60);
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[(0, 32, 1), (0, 1, 3), (15, 16, 3)])
+    );
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 7),
+        line(&[(0, 32, 1), (16, 17, 3), (31, 32, 3)])
+    );
+}
+
+#[test]
+fn test_tall_sprites() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT | 0x04);
+    // The lowest bit of the tile index is ignored
+    sprite(&mut mmu, 0, 0, 0, 3, 0x00);
+    sprite(&mut mmu, 1, 8, 0, 2, 0x40);
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[(0, 8, 2), (8, 16, 3)])
+    );
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 15),
+        line(&[(0, 8, 3), (8, 16, 2)])
+    );
+    assert_eq!(render_line(&mut gpu, &mmu, 16), line(&[]));
+}
+
+#[test]
+fn test_sprites_per_line() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    // Sprites outside of the screen horizontally count towards the limit
+    sprite(&mut mmu, 0, -8, 0, 1, 0);
+    for i in 1..11 {
+        sprite(&mut mmu, i, i as i16 * 10, 0, 1, 0);
+    }
+    let expected: Vec<(usize, usize, u8)> = (1..10).map(|i| (i * 10, i * 10 + 8, 1)).collect();
+    assert_eq!(render_line(&mut gpu, &mmu, 0), line(&expected));
+}
+
+#[test]
+fn test_sprite_priority() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    // The smaller X coordinate wins
+    sprite(&mut mmu, 0, 4, 0, 1, 0);
+    sprite(&mut mmu, 1, 0, 0, 2, 0);
+    // OAM order decides between equal X coordinates
+    sprite(&mut mmu, 2, 20, 0, 1, 0);
+    sprite(&mut mmu, 3, 20, 0, 2, 0);
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[(0, 8, 2), (8, 12, 1), (20, 28, 1)])
+    );
+}
+
+#[test]
+fn test_sprite_transparency() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    // Left half transparent
+    write_tile(&mut mmu, 0x8040, |x, _| if x < 4 { 0 } else { 3 });
+    sprite(&mut mmu, 0, 0, 0, 4, 0);
+    sprite(&mut mmu, 1, 2, 0, 1, 0);
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[(2, 4, 1), (4, 8, 3), (8, 10, 1)])
+    );
+}
+
+#[test]
+fn test_sprite_palettes() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    for color in 1..4 {
+        sprite(&mut mmu, color, color as i16 * 8, 0, color as u8, 0);
+        sprite(
+            &mut mmu,
+            color + 3,
+            40 + color as i16 * 8,
+            0,
+            color as u8,
+            0x10,
+        );
+    }
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[
+            (8, 16, 1),
+            (16, 24, 2),
+            (24, 32, 3),
+            (48, 56, 2),
+            (56, 64, 1),
+            (64, 72, 0),
+        ])
+    );
+}
+
+#[test]
+fn test_sprite_behind_background() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    // Background tile 4 is color 0 on the left and color 2 on the right
+    write_tile(&mut mmu, 0x8040, |x, _| if x < 4 { 0 } else { 2 });
+    mmu.write_u8(0x9800, 4);
+    sprite(&mut mmu, 0, 2, 0, 3, 0x80);
+    sprite(&mut mmu, 1, 2, 8, 3, 0x00);
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[(2, 4, 3), (4, 8, 2), (8, 10, 3)])
+    );
+    assert_eq!(render_line(&mut gpu, &mmu, 8), line(&[(2, 10, 3)]));
+}
+
+#[test]
+fn test_sprites_disabled() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT & !0x02);
+    sprite(&mut mmu, 0, 0, 0, 1, 0);
+    assert_eq!(render_line(&mut gpu, &mmu, 0), line(&[]));
+}
+
+#[test]
+fn test_sprites_ignore_scroll() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    mmu.write_u8(SCROLL_X_REGISTER, 100);
+    mmu.write_u8(SCROLL_Y_REGISTER, 200);
+    sprite(&mut mmu, 0, 10, 20, 1, 0);
+    assert_eq!(render_line(&mut gpu, &mmu, 20), line(&[(10, 18, 1)]));
+}
+
+/// Fills the tile map at `start` with tiles 1-3 by row,
+/// and writes tile 4 with color `x % 4` for column `x`
+fn window_tiles(mmu: &mut Mmu, start: u16) {
+    for row in 0..32 {
+        for column in 0..32 {
+            mmu.write_u8(start + row * 32 + column, (row % 3) as u8 + 1);
+        }
+    }
+    write_tile(mmu, 0x8040, |x, _| x % 4);
+}
+
+#[test]
+fn test_window() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT | 0x20);
+    window_tiles(&mut mmu, 0x9800);
+    mmu.write_u8(WINDOW_Y_REGISTER, 0);
+    mmu.write_u8(WINDOW_X_REGISTER, 87);
+    // The window covers the scrolled background
+    mmu.write_u8(SCROLL_X_REGISTER, 3);
+    mmu.write_u8(SCROLL_Y_REGISTER, 8);
+    let lines: Vec<Vec<u8>> = (0..9).map(|ly| render_line(&mut gpu, &mmu, ly)).collect();
+    assert_eq!(lines[0], line(&[(0, 80, 2), (80, 160, 1)]));
+    assert_eq!(lines[7], line(&[(0, 80, 2), (80, 160, 1)]));
+    assert_eq!(lines[8], line(&[(0, 80, 3), (80, 160, 2)]));
+}
+
+#[test]
+fn test_window_y() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT | 0x20);
+    window_tiles(&mut mmu, 0x9800);
+    mmu.write_u8(WINDOW_Y_REGISTER, 10);
+    mmu.write_u8(WINDOW_X_REGISTER, 7);
+    let lines: Vec<Vec<u8>> = (0..20).map(|ly| render_line(&mut gpu, &mmu, ly)).collect();
+    assert_eq!(lines[0], line(&[(0, 160, 1)]));
+    assert_eq!(lines[9], line(&[(0, 160, 2)]));
+    // The window starts from its first line at WY
+    assert_eq!(lines[10], line(&[(0, 160, 1)]));
+    assert_eq!(lines[17], line(&[(0, 160, 1)]));
+    assert_eq!(lines[18], line(&[(0, 160, 2)]));
+
+    // WY is compared on every line, the window is shown
+    // from the line where it matched until the end of the frame
+    mmu.write_u8(WINDOW_Y_REGISTER, 50);
+    render_line(&mut gpu, &mmu, 0);
+    mmu.write_u8(WINDOW_Y_REGISTER, 20);
+    for ly in 1..30 {
+        render_line(&mut gpu, &mmu, ly);
+    }
+    // Changing WY after the match does not restart the window,
+    // lines 20-29 were drawn so line 30 shows window tile row 1
+    mmu.write_u8(WINDOW_Y_REGISTER, 0);
+    assert_eq!(render_line(&mut gpu, &mmu, 30), line(&[(0, 160, 2)]));
+}
+
+#[test]
+fn test_window_line_counter() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT | 0x20);
+    window_tiles(&mut mmu, 0x9800);
+    mmu.write_u8(WINDOW_Y_REGISTER, 0);
+    mmu.write_u8(WINDOW_X_REGISTER, 7);
+    for ly in 0..4 {
+        render_line(&mut gpu, &mmu, ly);
+    }
+    // Hiding the window pauses the line counter
+    mmu.write_u8(LCDC_REGISTER, LCDC_DEFAULT);
+    for ly in 4..20 {
+        render_line(&mut gpu, &mmu, ly);
+    }
+    mmu.write_u8(WINDOW_X_REGISTER, 200);
+    mmu.write_u8(LCDC_REGISTER, LCDC_DEFAULT | 0x20);
+    for ly in 20..30 {
+        render_line(&mut gpu, &mmu, ly);
+    }
+    mmu.write_u8(WINDOW_X_REGISTER, 7);
+    for ly in 30..34 {
+        assert_eq!(render_line(&mut gpu, &mmu, ly), line(&[(0, 160, 1)]));
+    }
+    assert_eq!(render_line(&mut gpu, &mmu, 34), line(&[(0, 160, 2)]));
+}
+
+#[test]
+fn test_window_tilemap() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT | 0x60);
+    window_tiles(&mut mmu, 0x9C00);
+    mmu.write_u8(0x9C00, 4);
+    mmu.write_u8(WINDOW_Y_REGISTER, 0);
+    mmu.write_u8(WINDOW_X_REGISTER, 7);
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[
+            (0, 160, 1),
+            (0, 1, 0),
+            (2, 3, 2),
+            (3, 4, 3),
+            (4, 5, 0),
+            (6, 7, 2),
+            (7, 8, 3)
+        ])
+    );
+}
+
+#[test]
+fn test_window_x_below_7() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT | 0x20);
+    window_tiles(&mut mmu, 0x9800);
+    mmu.write_u8(0x9800, 4);
+    mmu.write_u8(WINDOW_Y_REGISTER, 0);
+    mmu.write_u8(WINDOW_X_REGISTER, 5);
+    // The first two pixels of the window are not shown
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[
+            (0, 160, 1),
+            (0, 1, 2),
+            (1, 2, 3),
+            (2, 3, 0),
+            (4, 5, 2),
+            (5, 6, 3)
+        ])
+    );
+}
+
+#[test]
+fn test_window_disabled_with_background() {
+    let (mut gpu, mut mmu) = init((LCDC_DEFAULT | 0x20) & !0x01);
+    window_tiles(&mut mmu, 0x9800);
+    mmu.write_u8(WINDOW_Y_REGISTER, 0);
+    mmu.write_u8(WINDOW_X_REGISTER, 7);
+    assert_eq!(render_line(&mut gpu, &mmu, 0), line(&[]));
+}
+
+#[test]
+fn test_sprites_over_window() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT | 0x20);
+    window_tiles(&mut mmu, 0x9800);
+    mmu.write_u8(WINDOW_Y_REGISTER, 0);
+    mmu.write_u8(WINDOW_X_REGISTER, 7);
+    sprite(&mut mmu, 0, 0, 0, 3, 0x00);
+    sprite(&mut mmu, 1, 8, 0, 3, 0x80);
+    assert_eq!(
+        render_line(&mut gpu, &mmu, 0),
+        line(&[(0, 160, 1), (0, 8, 3)])
+    );
+}
+
+/// Parses an image of color digits, one string per line
+fn golden(rows: &[&str]) -> Vec<Vec<u8>> {
+    rows.iter()
+        .map(|row| row.bytes().map(|c| c - b'0').collect())
+        .collect()
+}
+
+/// Renders the top left corner of the frame with the size of `expected`
+fn render_image(gpu: &mut Gpu, mmu: &Mmu, expected: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let width = expected[0].len();
+    (0..expected.len() as u8)
+        .map(|ly| render_line(gpu, mmu, ly)[..width].to_vec())
+        .collect()
+}
+
+/// Writes known tile patterns around both tile data areas
+/// and shows tiles 0x00, 0x80, 0x7F and 0xFF on the first row
+fn tile_patterns(mmu: &mut Mmu, tilemap_start: u16) {
+    let stripes = |x: u8, _| x % 4;
+    let bars = |_, y: u8| y % 4;
+    let diagonal = |x: u8, y: u8| (x + y) % 4;
+    let checker = |x: u8, y: u8| ((x ^ y) & 0x01) * 3;
+    write_tile(mmu, 0x8000, checker);
+    write_tile(mmu, 0x87F0, diagonal);
+    write_tile(mmu, 0x8800, bars);
+    write_tile(mmu, 0x8FF0, stripes);
+    write_tile(mmu, 0x9000, diagonal);
+    write_tile(mmu, 0x97F0, checker);
+    for (i, &tile) in [0x00, 0x80, 0x7F, 0xFF].iter().enumerate() {
+        mmu.write_u8(tilemap_start + i as u16, tile);
+    }
+}
+
+#[test]
+fn test_unsigned_tile_data() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    tile_patterns(&mut mmu, 0x9800);
+    let expected = golden(&[
+        "03030303000000000123012301230123",
+        "30303030111111111230123001230123",
+        "03030303222222222301230101230123",
+        "30303030333333333012301201230123",
+        "03030303000000000123012301230123",
+        "30303030111111111230123001230123",
+        "03030303222222222301230101230123",
+        "30303030333333333012301201230123",
+    ]);
+    assert_eq!(render_image(&mut gpu, &mmu, &expected), expected);
+}
+
+#[test]
+fn test_signed_tile_data() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT & !0x10);
+    tile_patterns(&mut mmu, 0x9800);
+    let expected = golden(&[
+        "01230123000000000303030301230123",
+        "12301230111111113030303001230123",
+        "23012301222222220303030301230123",
+        "30123012333333333030303001230123",
+        "01230123000000000303030301230123",
+        "12301230111111113030303001230123",
+        "23012301222222220303030301230123",
+        "30123012333333333030303001230123",
+    ]);
+    assert_eq!(render_image(&mut gpu, &mmu, &expected), expected);
+}
+
+#[test]
+fn test_background_tilemap_9c00() {
+    let (mut gpu, mut mmu) = init((LCDC_DEFAULT & !0x10) | 0x08);
+    window_tiles(&mut mmu, 0x9800);
+    tile_patterns(&mut mmu, 0x9C00);
+    // The scrolled background wraps around within the 0x9C00 map
+    mmu.write_u8(SCROLL_X_REGISTER, 252);
+    mmu.write_u8(SCROLL_Y_REGISTER, 2);
+    let expected = golden(&[
+        "230123012301222222220303030301230123",
+        "301230123012333333333030303001230123",
+        "012301230123000000000303030301230123",
+        "123012301230111111113030303001230123",
+        "230123012301222222220303030301230123",
+        "301230123012333333333030303001230123",
+        "012301230123012301230123012301230123",
+        "123012301230123012301230123012301230",
+    ]);
+    assert_eq!(render_image(&mut gpu, &mmu, &expected), expected);
+}
+
+#[test]
+fn test_background_palette() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    window_tiles(&mut mmu, 0x9800);
+    mmu.write_u8(0x9800, 4);
+    mmu.write_u8(BGP_REGISTER, 0x1B);
+    let mut expected = line(&[(8, 160, 2)]);
+    expected[..8].copy_from_slice(&[3, 2, 1, 0, 3, 2, 1, 0]);
+    assert_eq!(render_line(&mut gpu, &mmu, 0), expected);
+    // Fading out by mapping every color to white
+    mmu.write_u8(BGP_REGISTER, 0x00);
+    assert_eq!(render_line(&mut gpu, &mmu, 8), line(&[]));
+}
+
+#[test]
+fn test_background_disabled_is_white() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT & !0x01);
+    mmu.write_u8(BGP_REGISTER, 0xFF);
+    assert_eq!(render_line(&mut gpu, &mmu, 0), line(&[]));
+}
+
+#[test]
+fn test_sprite_priority_uses_color_index() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    // Background color 1 is shown as white, but still hides the sprite
+    mmu.write_u8(0x9800, 1);
+    mmu.write_u8(BGP_REGISTER, 0xE0);
+    sprite(&mut mmu, 0, 0, 0, 3, 0x80);
+    sprite(&mut mmu, 1, 8, 0, 3, 0x80);
+    assert_eq!(render_line(&mut gpu, &mmu, 0), line(&[(8, 16, 3)]));
+}
+
+#[test]
+fn test_scroll_per_scanline() {
+    let (mut gpu, mut mmu) = init(LCDC_DEFAULT);
+    window_tiles(&mut mmu, 0x9800);
+    for column in 0..32 {
+        mmu.write_u8(0x9800 + column, 4);
+    }
+    // Each line uses the scroll registers as they were on that line
+    mmu.write_u8(SCROLL_X_REGISTER, 1);
+    let first = render_line(&mut gpu, &mmu, 0);
+    mmu.write_u8(SCROLL_X_REGISTER, 2);
+    let second = render_line(&mut gpu, &mmu, 1);
+    assert_eq!(first[..4], [1, 2, 3, 0]);
+    assert_eq!(second[..4], [2, 3, 0, 1]);
+    assert_eq!(gpu.framebuffer[..4], [1, 2, 3, 0]);
+
+    // Split screen, the lower part shows another area of the map
+    mmu.write_u8(SCROLL_X_REGISTER, 0);
+    mmu.write_u8(SCROLL_Y_REGISTER, 0);
+    assert_eq!(render_line(&mut gpu, &mmu, 100), line(&[(0, 160, 1)]));
+    mmu.write_u8(SCROLL_Y_REGISTER, 164);
+    assert_eq!(render_line(&mut gpu, &mmu, 101), line(&[(0, 160, 2)]));
+}
+
+/// Counts the frames it has been given
+struct TestDisplay {
+    frames: usize,
+}
+impl Display for TestDisplay {
+    fn render_frame(&mut self, frame: &[u8]) {
+        assert_eq!(frame.len(), SCREEN_WIDTH * SCREEN_HEIGHT);
+        self.frames += 1;
+    }
+}
+
+/// Drives the Gpu like the CPU does until `condition` holds
+struct Runner {
+    gpu: Gpu,
+    mmu: Mmu,
+    display: TestDisplay,
+    cycles: usize,
+}
+impl Runner {
+    fn new(lcdc: u8) -> Runner {
+        let (gpu, mmu) = init(lcdc);
+        Runner {
+            gpu,
+            mmu,
+            display: TestDisplay { frames: 0 },
+            cycles: 0,
+        }
+    }
+    /// Runs one dot at a time until `condition` holds,
+    /// returns the number of dots it took
+    fn run_until<F: Fn(&Mmu) -> bool>(&mut self, condition: F) -> usize {
+        for dots in 1..1_000_000 {
+            self.cycles += 1;
+            self.gpu.step(&mut self.display, &mut self.mmu, 1);
+            if condition(&self.mmu) {
+                return dots;
+            }
+        }
+        panic!("Condition was not reached");
+    }
+    fn mode(&self) -> u8 {
+        self.mmu.read_u8(STAT_REGISTER) & 0x03
+    }
+    fn stat_requested(&self) -> bool {
+        self.mmu.read_u8(INTERRUPT_FLAG_REGISTER) & Interrupt::LcdStat.bit() != 0
+    }
+    fn clear_interrupts(&mut self) {
+        self.mmu.write_u8(INTERRUPT_FLAG_REGISTER, 0);
+    }
+}
+
+#[test]
+fn test_lyc_coincidence_flag() {
+    let mut runner = Runner::new(LCDC_DEFAULT);
+    runner.mmu.write_u8(LYC_REGISTER, 5);
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 4);
+    assert_eq!(runner.mmu.read_u8(STAT_REGISTER) & STAT_COINCIDENCE, 0);
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 5);
+    assert_ne!(runner.mmu.read_u8(STAT_REGISTER) & STAT_COINCIDENCE, 0);
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 6);
+    assert_eq!(runner.mmu.read_u8(STAT_REGISTER) & STAT_COINCIDENCE, 0);
+    // The interrupt is not enabled
+    assert!(!runner.stat_requested());
+}
+
+#[test]
+fn test_lyc_interrupt() {
+    let mut runner = Runner::new(LCDC_DEFAULT);
+    runner.mmu.write_u8(LYC_REGISTER, 5);
+    runner.mmu.write_u8(STAT_REGISTER, STAT_LYC_INTERRUPT);
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 4);
+    assert!(!runner.stat_requested());
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 5);
+    assert!(runner.stat_requested());
+    // Requested once per match
+    runner.clear_interrupts();
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 6);
+    assert!(!runner.stat_requested());
+}
+
+#[test]
+fn test_mode_interrupts() {
+    for &(source, mode) in [
+        (STAT_HBLANK_INTERRUPT, 0),
+        (STAT_VBLANK_INTERRUPT, 1),
+        (STAT_OAM_INTERRUPT, 2),
+    ]
+    .iter()
+    {
+        let mut runner = Runner::new(LCDC_DEFAULT);
+        runner.mmu.write_u8(STAT_REGISTER, source);
+        runner.run_until(|mmu| mmu.read_u8(STAT_REGISTER) & 0x03 != mode);
+        runner.clear_interrupts();
+        runner.run_until(|mmu| mmu.read_u8(STAT_REGISTER) & 0x03 == mode);
+        assert!(runner.stat_requested(), "mode {}", mode);
+        runner.clear_interrupts();
+        runner.run_until(|mmu| mmu.read_u8(STAT_REGISTER) & 0x03 == 3);
+        assert!(!runner.stat_requested(), "mode {}", mode);
+    }
+}
+
+#[test]
+fn test_stat_blocking() {
+    let mut runner = Runner::new(LCDC_DEFAULT);
+    runner.mmu.write_u8(LYC_REGISTER, 1);
+    runner
+        .mmu
+        .write_u8(STAT_REGISTER, STAT_LYC_INTERRUPT | STAT_HBLANK_INTERRUPT);
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 1);
+    assert!(runner.stat_requested());
+    runner.clear_interrupts();
+    // LY=LYC keeps the line high through the HBlank of line 1
+    runner.run_until(|mmu| mmu.read_u8(STAT_REGISTER) & 0x03 == 0);
+    assert_eq!(runner.mmu.read_u8(LY_REGISTER), 1);
+    assert!(!runner.stat_requested());
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 2);
+    runner.run_until(|mmu| mmu.read_u8(STAT_REGISTER) & 0x03 == 0);
+    assert!(runner.stat_requested());
+}
+
+#[test]
+fn test_lcd_off() {
+    let mut runner = Runner::new(LCDC_DEFAULT);
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 100);
+    runner.mmu.write_u8(LCDC_REGISTER, LCDC_DEFAULT & !0x80);
+    runner.run_until(|_| true);
+    assert_eq!(runner.mmu.read_u8(LY_REGISTER), 0);
+    assert_eq!(runner.mode(), 0);
+    let frames = runner.display.frames;
+    for _ in 0..100_000 {
+        runner.run_until(|_| true);
+        assert_eq!(runner.mmu.read_u8(LY_REGISTER), 0);
+    }
+    assert_eq!(runner.display.frames, frames);
+
+    runner.mmu.write_u8(LCDC_REGISTER, LCDC_DEFAULT);
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 1);
+}
+
+#[test]
+fn test_line_timing() {
+    let mut runner = Runner::new(LCDC_DEFAULT);
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 1);
+    assert_eq!(runner.mode(), 2);
+    assert_eq!(
+        runner.run_until(|mmu| mmu.read_u8(STAT_REGISTER) & 0x03 == 3),
+        80
+    );
+    assert_eq!(
+        runner.run_until(|mmu| mmu.read_u8(STAT_REGISTER) & 0x03 == 0),
+        172
+    );
+    assert_eq!(runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 2), 204);
+}
+
+#[test]
+fn test_frame_timing() {
+    let mut runner = Runner::new(LCDC_DEFAULT);
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 144);
+    assert_eq!(runner.cycles, 144 * 456);
+    assert_eq!(runner.mode(), 1);
+    assert_eq!(runner.display.frames, 1);
+    // The VBlank interrupt is requested when VBlank starts
+    let interrupts = runner.mmu.read_u8(INTERRUPT_FLAG_REGISTER);
+    assert_ne!(interrupts & Interrupt::VBlank.bit(), 0);
+
+    runner.clear_interrupts();
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 153);
+    assert_eq!(runner.mode(), 1);
+    let dots = runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 0);
+    assert_eq!(dots, 456);
+    assert_eq!(runner.mode(), 2);
+    assert_eq!(runner.cycles, 154 * 456);
+    assert_eq!(runner.mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x1F, 0);
+
+    runner.run_until(|mmu| mmu.read_u8(LY_REGISTER) == 144);
+    assert_eq!(runner.cycles, 154 * 456 + 144 * 456);
+    assert_eq!(runner.display.frames, 2);
+}
+
+/// Length of the pixel transfer on line 0
+fn transfer_dots(runner: &mut Runner) -> usize {
+    runner.run_until(|mmu| mmu.read_u8(STAT_REGISTER) & 0x03 == 3);
+    runner.run_until(|mmu| mmu.read_u8(STAT_REGISTER) & 0x03 == 0)
+}
+
+#[test]
+fn test_transfer_length_scroll() {
+    let mut runner = Runner::new(LCDC_DEFAULT);
+    runner.mmu.write_u8(SCROLL_X_REGISTER, 0x13);
+    assert_eq!(transfer_dots(&mut runner), 172 + 3);
+}
+
+#[test]
+fn test_transfer_length_window() {
+    let mut runner = Runner::new(LCDC_DEFAULT | 0x20);
+    runner.mmu.write_u8(WINDOW_Y_REGISTER, 0);
+    runner.mmu.write_u8(WINDOW_X_REGISTER, 50);
+    assert_eq!(transfer_dots(&mut runner), 172 + 6);
+}
+
+#[test]
+fn test_transfer_length_sprites() {
+    let mut runner = Runner::new(LCDC_DEFAULT);
+    // Aligned with a tile, 6 dots and 5 for waiting for the tile
+    sprite(&mut runner.mmu, 0, 16, 0, 1, 0);
+    // Same tile, only the 6 dots
+    sprite(&mut runner.mmu, 1, 20, 0, 1, 0);
+    // The tile is almost fetched already
+    sprite(&mut runner.mmu, 2, 46, 0, 1, 0);
+    // Not on the line
+    sprite(&mut runner.mmu, 3, 80, 8, 1, 0);
+    assert_eq!(transfer_dots(&mut runner), 172 + 11 + 6 + 6);
+}
+
+#[test]
+fn test_sprites_disabled_transfer_length() {
+    let mut runner = Runner::new(LCDC_DEFAULT & !0x02);
+    sprite(&mut runner.mmu, 0, 16, 0, 1, 0);
+    assert_eq!(transfer_dots(&mut runner), 172);
+}
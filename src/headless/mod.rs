@@ -0,0 +1,50 @@
+extern crate png;
+
+#[cfg(test)]
+mod tests;
+
+use gameboy::display::Palette;
+use gameboy::gpu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use gameboy::runner::{self, Stop, StopConditions};
+use gameboy::serial::SerialBuffer;
+use gameboy::GameBoy;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// What to run and what to write without a display
+#[derive(Debug, Default)]
+pub struct Options {
+    pub stop: StopConditions,
+    /// Write the final frame to a PNG file
+    pub screenshot: Option<PathBuf>,
+    pub palette: Palette,
+}
+
+/// Writes a frame of shades as an RGB PNG image
+pub fn write_png<P: AsRef<Path>>(path: P, frame: &[u8], palette: &Palette) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let pixels: Vec<u8> = frame.iter().flat_map(|&shade| palette.rgb(shade)).collect();
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(io::Error::other)
+}
+
+/// Runs `gameboy` as configured in `options`, then writes the screenshot
+/// and prints the serial output. Returns the reason the emulation stopped.
+pub fn main(mut gameboy: GameBoy, serial: &SerialBuffer, options: &Options) -> io::Result<Stop> {
+    let (stop, output) = runner::run(&mut gameboy, serial, &options.stop);
+    info!("Stopped after {} cycles: {:?}", gameboy.cycles(), stop);
+    if let Some(ref path) = options.screenshot {
+        write_png(path, gameboy.framebuffer(), &options.palette)?;
+    }
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    stdout.write_all(&output)?;
+    stdout.flush()?;
+    Ok(stop)
+}
@@ -0,0 +1,17 @@
+use super::*;
+
+#[test]
+fn test_write_png() {
+    let mut frame = vec![0; SCREEN_WIDTH * SCREEN_HEIGHT];
+    frame[1] = 3;
+    let path = std::env::temp_dir().join(format!("gameboy-test-{}.png", std::process::id()));
+    write_png(&path, &frame, &Palette::GREEN).unwrap();
+
+    let decoder = png::Decoder::new(File::open(&path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((info.width, info.height), (160, 144));
+    assert_eq!(&pixels[..6], &[0x9B, 0xBC, 0x0F, 0x0F, 0x38, 0x0F]);
+}
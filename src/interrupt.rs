@@ -0,0 +1,45 @@
+pub const INTERRUPT_FLAG_REGISTER: u16 = 0xFF0F;
+pub const INTERRUPT_ENABLE_REGISTER: u16 = 0xFFFF;
+
+/// Interrupt sources in priority order
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interrupt {
+    VBlank,
+    LcdStat,
+    Timer,
+    Serial,
+    Joypad,
+}
+impl Interrupt {
+    pub const ALL: [Interrupt; 5] = [
+        Interrupt::VBlank,
+        Interrupt::LcdStat,
+        Interrupt::Timer,
+        Interrupt::Serial,
+        Interrupt::Joypad,
+    ];
+
+    /// Bit of the interrupt in IF and IE registers
+    #[inline]
+    pub fn bit(self) -> u8 {
+        match self {
+            Interrupt::VBlank => 0x01,
+            Interrupt::LcdStat => 0x02,
+            Interrupt::Timer => 0x04,
+            Interrupt::Serial => 0x08,
+            Interrupt::Joypad => 0x10,
+        }
+    }
+
+    /// Address of the interrupt handler
+    #[inline]
+    pub fn vector(self) -> u16 {
+        match self {
+            Interrupt::VBlank => 0x40,
+            Interrupt::LcdStat => 0x48,
+            Interrupt::Timer => 0x50,
+            Interrupt::Serial => 0x58,
+            Interrupt::Joypad => 0x60,
+        }
+    }
+}
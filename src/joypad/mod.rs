@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests;
+
+#[cfg(target_os = "unknown")]
+use wasm_bindgen::prelude::*;
+
+pub const JOYPAD_REGISTER: u16 = 0xFF00;
+
+const SELECT_DIRECTIONS: u8 = 0x10;
+const SELECT_ACTIONS: u8 = 0x20;
+
+#[cfg_attr(target_os = "unknown", wasm_bindgen)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+impl Button {
+    /// Whether the button is read through the direction select line
+    #[inline]
+    fn is_direction(self) -> bool {
+        matches!(
+            self,
+            Button::Right | Button::Left | Button::Up | Button::Down
+        )
+    }
+
+    /// Bit of the button in the lower nibble of P1
+    #[inline]
+    fn bit(self) -> u8 {
+        match self {
+            Button::Right | Button::A => 0x01,
+            Button::Left | Button::B => 0x02,
+            Button::Up | Button::Select => 0x04,
+            Button::Down | Button::Start => 0x08,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ButtonEvent {
+    Pressed(Button),
+    Released(Button),
+}
+
+/// P1 register.
+///
+/// Bits 4 and 5 select the direction and action buttons, and the lower
+/// nibble reads the selected buttons. Both selects and buttons are
+/// active low, so a pressed button reads as 0.
+#[derive(Debug, Default)]
+pub struct Joypad {
+    /// Select bits as written by the game
+    select: u8,
+    /// Pressed direction buttons, 1 for pressed
+    directions: u8,
+    /// Pressed action buttons, 1 for pressed
+    actions: u8,
+}
+
+impl Joypad {
+    pub fn new() -> Joypad {
+        Joypad::default()
+    }
+
+    /// Selected buttons that are pressed, 1 for pressed
+    fn lines(&self) -> u8 {
+        let mut lines = 0;
+        if self.select & SELECT_DIRECTIONS == 0 {
+            lines |= self.directions;
+        }
+        if self.select & SELECT_ACTIONS == 0 {
+            lines |= self.actions;
+        }
+        lines
+    }
+
+    /// Applies the change and returns true if any input line
+    /// went from high to low, which requests the joypad interrupt.
+    fn update<F: FnOnce(&mut Joypad)>(&mut self, change: F) -> bool {
+        let before = self.lines();
+        change(self);
+        self.lines() & !before != 0
+    }
+
+    pub fn press(&mut self, button: Button) -> bool {
+        self.update(|j| {
+            if button.is_direction() {
+                j.directions |= button.bit();
+            } else {
+                j.actions |= button.bit();
+            }
+        })
+    }
+
+    pub fn release(&mut self, button: Button) -> bool {
+        self.update(|j| {
+            if button.is_direction() {
+                j.directions &= !button.bit();
+            } else {
+                j.actions &= !button.bit();
+            }
+        })
+    }
+
+    pub fn read(&self) -> u8 {
+        0xC0 | self.select | (!self.lines() & 0x0F)
+    }
+
+    pub fn write(&mut self, value: u8) -> bool {
+        self.update(|j| j.select = value & (SELECT_DIRECTIONS | SELECT_ACTIONS))
+    }
+}
@@ -0,0 +1,77 @@
+use joypad::*;
+
+#[test]
+fn test_nothing_pressed() {
+    let mut joypad = Joypad::new();
+    assert_eq!(joypad.read(), 0xCF);
+    joypad.write(0x30);
+    assert_eq!(joypad.read(), 0xFF);
+}
+
+#[test]
+fn test_select_lines() {
+    let mut joypad = Joypad::new();
+    joypad.press(Button::Down);
+    joypad.press(Button::A);
+
+    joypad.write(0x20);
+    assert_eq!(joypad.read(), 0xE7);
+    joypad.write(0x10);
+    assert_eq!(joypad.read(), 0xDE);
+    joypad.write(0x30);
+    assert_eq!(joypad.read(), 0xFF);
+    joypad.write(0x00);
+    assert_eq!(joypad.read(), 0xC6);
+}
+
+#[test]
+fn test_button_bits() {
+    let buttons = [
+        (Button::Right, 0x20, 0x0E),
+        (Button::Left, 0x20, 0x0D),
+        (Button::Up, 0x20, 0x0B),
+        (Button::Down, 0x20, 0x07),
+        (Button::A, 0x10, 0x0E),
+        (Button::B, 0x10, 0x0D),
+        (Button::Select, 0x10, 0x0B),
+        (Button::Start, 0x10, 0x07),
+    ];
+    for &(button, select, expected) in buttons.iter() {
+        let mut joypad = Joypad::new();
+        joypad.write(select);
+        joypad.press(button);
+        assert_eq!(joypad.read() & 0x0F, expected, "{:?}", button);
+        joypad.release(button);
+        assert_eq!(joypad.read() & 0x0F, 0x0F, "{:?}", button);
+    }
+}
+
+#[test]
+fn test_interrupt_on_press() {
+    let mut joypad = Joypad::new();
+    joypad.write(0x20);
+    assert!(joypad.press(Button::Left));
+    // Already low
+    assert!(!joypad.press(Button::Left));
+    assert!(!joypad.release(Button::Left));
+}
+
+#[test]
+fn test_no_interrupt_when_not_selected() {
+    let mut joypad = Joypad::new();
+    joypad.write(0x20);
+    assert!(!joypad.press(Button::Start));
+    assert!(!joypad.release(Button::Start));
+
+    joypad.write(0x30);
+    assert!(!joypad.press(Button::Up));
+}
+
+#[test]
+fn test_interrupt_on_select() {
+    let mut joypad = Joypad::new();
+    joypad.write(0x30);
+    joypad.press(Button::Start);
+    assert!(joypad.write(0x10));
+    assert!(!joypad.write(0x10));
+}
@@ -1,11 +1,22 @@
 #[macro_use]
 extern crate log;
 
+pub mod apu;
 pub mod cartridge;
 pub mod cpu;
 pub mod display;
+pub mod dma;
+pub mod error;
+pub mod gameboy;
 pub mod gpu;
+pub mod interrupt;
+pub mod joypad;
 pub mod mmu;
+pub mod runner;
+pub mod serial;
+pub mod timer;
+
+pub use gameboy::{GameBoy, GameBoyBuilder, Model};
 
 #[cfg(target_os = "unknown")]
 extern crate wasm_bindgen;
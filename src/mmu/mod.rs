@@ -0,0 +1,397 @@
+#[cfg(test)]
+mod tests;
+
+use apu::{Apu, DEFAULT_SAMPLE_RATE, NR10_REGISTER, NR52_REGISTER, WAVE_RAM_END};
+use cartridge::rtc::{Rtc, SystemClock};
+use cartridge::{self, Cartridge, Header, RomOnly, MBC1, MBC2, MBC3, MBC5};
+use dma::{Dma, DMA_REGISTER, OAM_START};
+use error::GameboyError;
+use gpu::{
+    LcdRegisters, BGP_REGISTER, LCDC_REGISTER, OBP0_REGISTER, OBP1_REGISTER, WINDOW_X_REGISTER,
+};
+use interrupt::{Interrupt, INTERRUPT_ENABLE_REGISTER, INTERRUPT_FLAG_REGISTER};
+use joypad::{Button, Joypad, JOYPAD_REGISTER};
+use serial::{Serial, SerialLink, SB_REGISTER, SC_REGISTER};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use timer::{Timer, DIV_REGISTER, TAC_REGISTER};
+
+const ROM_END: u16 = 0x7FFF;
+const VRAM_START: u16 = 0x8000;
+const VRAM_END: u16 = 0x9FFF;
+const EXTERNAL_RAM_START: u16 = 0xA000;
+const EXTERNAL_RAM_END: u16 = 0xBFFF;
+const WRAM_START: u16 = 0xC000;
+const WRAM_END: u16 = 0xDFFF;
+const ECHO_RAM_START: u16 = 0xE000;
+const ECHO_RAM_END: u16 = 0xFDFF;
+const OAM_END: u16 = 0xFE9F;
+const UNUSABLE_END: u16 = 0xFEFF;
+const IO_START: u16 = 0xFF00;
+const IO_END: u16 = 0xFF7F;
+const HRAM_START: u16 = 0xFF80;
+const HRAM_END: u16 = 0xFFFE;
+
+const BOOT_ROM_REGISTER: u16 = 0xFF50;
+
+fn is_in_hram(addr: u16) -> bool {
+    (HRAM_START..=HRAM_END).contains(&addr)
+}
+
+pub struct Mmu {
+    /// Backs the cartridge areas when no cartridge is loaded
+    rom: Box<[u8]>,
+    vram: Box<[u8]>,
+    wram: Box<[u8]>,
+    oam: Box<[u8]>,
+    hram: Box<[u8]>,
+    boot: [u8; 256],
+    boot_rom_finished: bool,
+    cartridge: Option<Box<dyn Cartridge>>,
+    header: Option<Header>,
+    battery: bool,
+    timer: Timer,
+    joypad: Joypad,
+    apu: Apu,
+    lcd: LcdRegisters,
+    dma: Option<Dma>,
+    /// Last value written to the DMA register
+    dma_page: u8,
+    serial: Serial,
+    interrupt_flag: u8,
+    interrupt_enable: u8,
+}
+
+impl Default for Mmu {
+    fn default() -> Mmu {
+        Mmu::new()
+    }
+}
+
+impl Mmu {
+    /// Creates the Mmu in the state the boot ROM leaves it in
+    pub fn new() -> Mmu {
+        let mut mmu = Mmu::with_boot_rom_data([0; 256]);
+        // The trigger bits are left out to not start the channels again
+        let sound = [
+            (NR52_REGISTER, 0xF1),
+            (0xFF10, 0x80),
+            (0xFF11, 0xBF),
+            (0xFF12, 0xF3),
+            (0xFF14, 0x3F),
+            (0xFF16, 0x3F),
+            (0xFF19, 0x3F),
+            (0xFF1A, 0x7F),
+            (0xFF1B, 0xFF),
+            (0xFF1C, 0x9F),
+            (0xFF1E, 0x3F),
+            (0xFF20, 0xFF),
+            (0xFF23, 0x3F),
+            (0xFF24, 0x77),
+            (0xFF25, 0xF3),
+        ];
+        for &(addr, value) in sound.iter() {
+            mmu.apu.write(addr, value);
+        }
+        let lcd = [
+            (LCDC_REGISTER, 0x91),
+            (BGP_REGISTER, 0xFC),
+            (OBP0_REGISTER, 0xFF),
+            (OBP1_REGISTER, 0xFF),
+        ];
+        for &(addr, value) in lcd.iter() {
+            mmu.lcd.write(addr, value);
+        }
+        mmu.boot_rom_finished = true;
+        mmu
+    }
+
+    /// Creates the Mmu with the boot ROM mapped to 0x0000-0x00FF
+    pub fn with_boot_rom<P: AsRef<Path>>(path: P) -> Result<Mmu, GameboyError> {
+        let mut data = vec![];
+        fs::File::open(path)?.read_to_end(&mut data)?;
+        if data.len() != 256 {
+            return Err(GameboyError::InvalidBootRom(data.len()));
+        }
+        let mut boot = [0; 256];
+        boot.copy_from_slice(&data);
+        Ok(Mmu::with_boot_rom_data(boot))
+    }
+
+    pub fn with_boot_rom_data(boot: [u8; 256]) -> Mmu {
+        Mmu {
+            rom: vec![0; ROM_END as usize + 1].into_boxed_slice(),
+            vram: vec![0; (VRAM_END - VRAM_START) as usize + 1].into_boxed_slice(),
+            wram: vec![0; (WRAM_END - WRAM_START) as usize + 1].into_boxed_slice(),
+            oam: vec![0; (OAM_END - OAM_START) as usize + 1].into_boxed_slice(),
+            hram: vec![0; (HRAM_END - HRAM_START) as usize + 1].into_boxed_slice(),
+            boot,
+            boot_rom_finished: false,
+            cartridge: None,
+            header: None,
+            battery: false,
+            timer: Timer::new(),
+            joypad: Joypad::new(),
+            apu: Apu::new(DEFAULT_SAMPLE_RATE),
+            lcd: LcdRegisters::new(),
+            dma: None,
+            dma_page: 0,
+            serial: Serial::default(),
+            interrupt_flag: 0,
+            interrupt_enable: 0,
+        }
+    }
+
+    #[inline]
+    pub fn boot_rom_finished(&self) -> bool {
+        self.boot_rom_finished
+    }
+
+    pub fn load_cartridge<P: AsRef<Path>>(&mut self, path: P) -> Result<(), GameboyError> {
+        let file = fs::File::open(path)?;
+        self.load_cartridge_data(file)
+    }
+
+    pub fn load_cartridge_data<R: Read>(&mut self, mut data: R) -> Result<(), GameboyError> {
+        let mut rom = vec![];
+        data.read_to_end(&mut rom)?;
+        let header = Header::parse(&rom)?;
+        info!("Loaded cartridge {}", header);
+        if !header.global_checksum_valid {
+            warn!("Global checksum does not match");
+        }
+        if header.rom_size != rom.len() {
+            warn!(
+                "ROM is {} bytes, header specifies {} bytes",
+                rom.len(),
+                header.rom_size
+            );
+        }
+
+        let ram_size = header.ram_size;
+        let cartridge: Box<dyn Cartridge> = match header.cartridge_type {
+            0x00 => Box::new(RomOnly::new(rom)),
+            0x01..=0x03 => Box::new(MBC1::new(rom, ram_size)),
+            0x05 | 0x06 => Box::new(MBC2::new(rom)),
+            ct @ 0x0F..=0x13 => {
+                let rtc = if ct <= 0x10 {
+                    Some(Rtc::new(Box::new(SystemClock)))
+                } else {
+                    None
+                };
+                Box::new(MBC3::new(rom, ram_size, rtc))
+            }
+            ct @ 0x19..=0x1E => {
+                let has_rumble = ct >= 0x1C;
+                Box::new(MBC5::new(rom, ram_size, has_rumble))
+            }
+            ct => return Err(GameboyError::UnsupportedCartridge(ct)),
+        };
+        self.cartridge = Some(cartridge);
+        self.battery = cartridge::has_battery(header.cartridge_type);
+        self.header = Some(header);
+        Ok(())
+    }
+
+    /// Header of the loaded cartridge
+    #[inline]
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Whether the cartridge keeps its RAM contents with a battery
+    #[inline]
+    pub fn has_battery(&self) -> bool {
+        self.battery
+    }
+
+    /// Battery-backed cartridge memory to be written to a .sav file
+    pub fn save_data(&mut self) -> Option<Vec<u8>> {
+        if !self.battery {
+            return None;
+        }
+        self.cartridge.as_mut().map(|c| c.save_data())
+    }
+
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        if let Some(ref mut cartridge) = self.cartridge {
+            cartridge.load_save_data(data);
+        }
+    }
+
+    /// Advances the peripherals by `cycles` clock cycles
+    pub fn step(&mut self, cycles: usize) {
+        if self.timer.step(cycles) {
+            self.request_interrupt(Interrupt::Timer);
+        }
+        if self.serial.step(cycles) {
+            self.request_interrupt(Interrupt::Serial);
+        }
+        self.apu.step(cycles);
+        if let Some(mut dma) = self.dma.take() {
+            for offset in dma.step(cycles) {
+                let value = self.read_u8_direct(dma.source() + offset);
+                self.oam[offset as usize] = value;
+            }
+            if !dma.finished() {
+                self.dma = Some(dma);
+            }
+        }
+    }
+
+    /// An OAM DMA transfer is running
+    #[inline]
+    pub fn dma_active(&self) -> bool {
+        self.dma.is_some()
+    }
+
+    pub fn apu_mut(&mut self) -> &mut Apu {
+        &mut self.apu
+    }
+
+    /// Connects the serial port to `link`
+    pub fn set_serial_link(&mut self, link: Box<dyn SerialLink>) {
+        self.serial.set_link(link);
+    }
+
+    pub fn lcd_mut(&mut self) -> &mut LcdRegisters {
+        &mut self.lcd
+    }
+
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt_flag |= interrupt.bit();
+    }
+
+    pub fn press_button(&mut self, button: Button) {
+        if self.joypad.press(button) {
+            self.request_interrupt(Interrupt::Joypad);
+        }
+    }
+
+    pub fn release_button(&mut self, button: Button) {
+        if self.joypad.release(button) {
+            self.request_interrupt(Interrupt::Joypad);
+        }
+    }
+
+    pub fn clear_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt_flag &= !interrupt.bit();
+    }
+
+    /// Highest priority interrupt that is both requested and enabled
+    pub fn pending_interrupt(&self) -> Option<Interrupt> {
+        let pending = self.interrupt_flag & self.interrupt_enable;
+        Interrupt::ALL
+            .iter()
+            .cloned()
+            .find(|interrupt| pending & interrupt.bit() != 0)
+    }
+
+    #[cfg(test)]
+    pub fn set_bytes(&mut self, bytes: &[u8]) {
+        for (i, b) in bytes.iter().enumerate() {
+            self.rom[i] = *b;
+        }
+    }
+    /// Write from the CPU, only HRAM can be written during OAM DMA
+    pub fn write_u8(&mut self, addr: u16, value: u8) {
+        if self.dma.is_some() && !is_in_hram(addr) {
+            trace!("Ignoring WRITE[0x{:2X}] during DMA", addr);
+            return;
+        }
+        self.write_u8_direct(addr, value);
+    }
+    /// Write without the bus restrictions of the CPU
+    pub fn write_u8_direct(&mut self, addr: u16, value: u8) {
+        trace!("WRITE[0x{:2X}] = 0x{:02X}", addr, value);
+        match addr {
+            // Writes to the ROM area program the memory bank controller.
+            // Without a cartridge the area is backed by plain memory.
+            0..=ROM_END | EXTERNAL_RAM_START..=EXTERNAL_RAM_END => match self.cartridge {
+                Some(ref mut cartridge) => cartridge.write_u8(addr, value),
+                None if addr <= ROM_END => self.rom[addr as usize] = value,
+                None => {}
+            },
+            VRAM_START..=VRAM_END => self.vram[(addr - VRAM_START) as usize] = value,
+            WRAM_START..=WRAM_END => self.wram[(addr - WRAM_START) as usize] = value,
+            ECHO_RAM_START..=ECHO_RAM_END => self.wram[(addr - ECHO_RAM_START) as usize] = value,
+            OAM_START..=OAM_END => self.oam[(addr - OAM_START) as usize] = value,
+            IO_START..=IO_END => self.write_io(addr, value),
+            HRAM_START..=HRAM_END => self.hram[(addr - HRAM_START) as usize] = value,
+            INTERRUPT_ENABLE_REGISTER => self.interrupt_enable = value,
+            // 0xFEA0-0xFEFF is not usable
+            _ => {}
+        }
+    }
+    fn write_io(&mut self, addr: u16, value: u8) {
+        match addr {
+            JOYPAD_REGISTER => {
+                let interrupt = self.joypad.write(value);
+                if interrupt {
+                    self.request_interrupt(Interrupt::Joypad);
+                }
+            }
+            SB_REGISTER | SC_REGISTER => self.serial.write(addr, value),
+            DIV_REGISTER..=TAC_REGISTER => self.timer.write(addr, value),
+            INTERRUPT_FLAG_REGISTER => self.interrupt_flag = value & 0x1F,
+            NR10_REGISTER..=WAVE_RAM_END => self.apu.write(addr, value),
+            DMA_REGISTER => {
+                self.dma_page = value;
+                self.dma = Some(Dma::new(value));
+            }
+            LCDC_REGISTER..=WINDOW_X_REGISTER => self.lcd.write(addr, value),
+            // The boot ROM can't be mapped back
+            BOOT_ROM_REGISTER if value != 0 => self.boot_rom_finished = true,
+            _ => {}
+        }
+    }
+    pub fn write_u16(&mut self, addr: u16, value: u16) {
+        self.write_u8(addr, (value & 0xFF) as u8);
+        self.write_u8(addr + 1, ((value >> 8) & 0xFF) as u8);
+    }
+    /// Read from the CPU, only HRAM can be read during OAM DMA
+    pub fn read_u8(&self, addr: u16) -> u8 {
+        if self.dma.is_some() && !is_in_hram(addr) {
+            return 0xFF;
+        }
+        self.read_u8_direct(addr)
+    }
+    /// Read without the bus restrictions of the CPU
+    pub fn read_u8_direct(&self, addr: u16) -> u8 {
+        match addr {
+            0..=0xFF if !self.boot_rom_finished => self.boot[addr as usize],
+            0..=ROM_END | EXTERNAL_RAM_START..=EXTERNAL_RAM_END => match self.cartridge {
+                Some(ref cartridge) => cartridge.read_u8(addr),
+                None if addr <= ROM_END => self.rom[addr as usize],
+                None => 0xFF,
+            },
+            VRAM_START..=VRAM_END => self.vram[(addr - VRAM_START) as usize],
+            WRAM_START..=WRAM_END => self.wram[(addr - WRAM_START) as usize],
+            ECHO_RAM_START..=ECHO_RAM_END => self.wram[(addr - ECHO_RAM_START) as usize],
+            OAM_START..=OAM_END => self.oam[(addr - OAM_START) as usize],
+            0xFEA0..=UNUSABLE_END => 0xFF,
+            IO_START..=IO_END => self.read_io(addr),
+            HRAM_START..=HRAM_END => self.hram[(addr - HRAM_START) as usize],
+            INTERRUPT_ENABLE_REGISTER => self.interrupt_enable,
+        }
+    }
+    /// Unused registers and bits read as 1
+    fn read_io(&self, addr: u16) -> u8 {
+        match addr {
+            JOYPAD_REGISTER => self.joypad.read(),
+            SB_REGISTER | SC_REGISTER => self.serial.read(addr),
+            DIV_REGISTER..=TAC_REGISTER => self.timer.read(addr),
+            INTERRUPT_FLAG_REGISTER => self.interrupt_flag | 0xE0,
+            NR10_REGISTER..=WAVE_RAM_END => self.apu.read(addr),
+            DMA_REGISTER => self.dma_page,
+            LCDC_REGISTER..=WINDOW_X_REGISTER => self.lcd.read(addr),
+            _ => 0xFF,
+        }
+    }
+    pub fn read_u16(&self, addr: u16) -> u16 {
+        let l = self.read_u8(addr);
+        let h = self.read_u8(addr + 1);
+        ((h as u16) << 8) | l as u16
+    }
+}
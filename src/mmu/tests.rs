@@ -0,0 +1,114 @@
+use gpu::{LY_REGISTER, STAT_REGISTER};
+use interrupt::{INTERRUPT_ENABLE_REGISTER, INTERRUPT_FLAG_REGISTER};
+use mmu::*;
+use serial::{CaptureLink, SerialBuffer, CYCLES_PER_TRANSFER, SB_REGISTER, SC_REGISTER};
+
+#[test]
+fn test_echo_ram() {
+    let mut mmu = Mmu::new();
+    mmu.write_u8(0xC000, 0x12);
+    assert_eq!(mmu.read_u8(0xE000), 0x12);
+    mmu.write_u8(0xFDFF, 0x34);
+    assert_eq!(mmu.read_u8(0xDDFF), 0x34);
+    // The echo ends at 0xFDFF, 0xDE00-0xDFFF are not mirrored
+    mmu.write_u8(0xDE00, 0x56);
+    assert_eq!(mmu.read_u8(0xFE00), 0x00);
+    mmu.write_u8(0xFE00, 0x78);
+    assert_eq!(mmu.read_u8(0xDE00), 0x56);
+}
+
+#[test]
+fn test_memory_regions() {
+    let mut mmu = Mmu::new();
+    for &addr in [
+        0x8000, 0x9FFF, 0xC000, 0xDFFF, 0xFE00, 0xFE9F, 0xFF80, 0xFFFE,
+    ]
+    .iter()
+    {
+        mmu.write_u8(addr, 0xA5);
+        assert_eq!(mmu.read_u8(addr), 0xA5, "{:04X}", addr);
+        assert_eq!(mmu.read_u8(addr ^ 0x0001), 0x00, "{:04X}", addr ^ 0x0001);
+    }
+}
+
+#[test]
+fn test_unusable_area() {
+    let mut mmu = Mmu::new();
+    mmu.write_u8(0xFEA0, 0x00);
+    mmu.write_u8(0xFEFF, 0x00);
+    assert_eq!(mmu.read_u8(0xFEA0), 0xFF);
+    assert_eq!(mmu.read_u8(0xFEFF), 0xFF);
+}
+
+#[test]
+fn test_external_ram_without_cartridge() {
+    let mut mmu = Mmu::new();
+    mmu.write_u8(0xA000, 0x12);
+    assert_eq!(mmu.read_u8(0xA000), 0xFF);
+}
+
+#[test]
+fn test_unused_io_registers() {
+    let mut mmu = Mmu::new();
+    for &addr in [0xFF03, 0xFF08, 0xFF0E, 0xFF4C, 0xFF51, 0xFF7F].iter() {
+        mmu.write_u8(addr, 0x00);
+        assert_eq!(mmu.read_u8(addr), 0xFF, "{:04X}", addr);
+    }
+}
+
+#[test]
+fn test_unused_register_bits() {
+    let mut mmu = Mmu::new();
+    mmu.write_u8(INTERRUPT_FLAG_REGISTER, 0x00);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER), 0xE0);
+    mmu.write_u8(STAT_REGISTER, 0x00);
+    assert_eq!(mmu.read_u8(STAT_REGISTER), 0x80);
+    mmu.write_u8(0xFF02, 0x00);
+    assert_eq!(mmu.read_u8(0xFF02), 0x7E);
+    // All bits of IE can be written
+    mmu.write_u8(INTERRUPT_ENABLE_REGISTER, 0xFF);
+    assert_eq!(mmu.read_u8(INTERRUPT_ENABLE_REGISTER), 0xFF);
+}
+
+#[test]
+fn test_read_only_lcd_bits() {
+    let mut mmu = Mmu::new();
+    mmu.lcd_mut().set_ly(42);
+    mmu.lcd_mut().set_stat_status(0x06);
+    mmu.write_u8(LY_REGISTER, 0);
+    assert_eq!(mmu.read_u8(LY_REGISTER), 42);
+    mmu.write_u8(STAT_REGISTER, 0xFF);
+    assert_eq!(mmu.read_u8(STAT_REGISTER), 0xFE);
+    mmu.write_u8(STAT_REGISTER, 0x00);
+    assert_eq!(mmu.read_u8(STAT_REGISTER), 0x86);
+}
+
+#[test]
+fn test_boot_rom_is_unmapped() {
+    let mut boot = [0; 256];
+    boot[0] = 0x31;
+    let mut mmu = Mmu::with_boot_rom_data(boot);
+    mmu.set_bytes(&[0xC3]);
+    assert_eq!(mmu.read_u8(0x0000), 0x31);
+    assert_eq!(mmu.read_u8(0xFF50), 0xFF);
+    mmu.write_u8(0xFF50, 0x01);
+    assert_eq!(mmu.read_u8(0x0000), 0xC3);
+    mmu.write_u8(0xFF50, 0x00);
+    assert_eq!(mmu.read_u8(0x0000), 0xC3);
+}
+
+#[test]
+fn test_serial_interrupt() {
+    let mut mmu = Mmu::new();
+    let output = SerialBuffer::new();
+    mmu.set_serial_link(Box::new(CaptureLink::new(output.clone())));
+    mmu.write_u8(SB_REGISTER, b'O');
+    mmu.write_u8(SC_REGISTER, 0x81);
+    mmu.step(CYCLES_PER_TRANSFER - 4);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x08, 0);
+    mmu.step(4);
+    assert_eq!(output.take(), b"O");
+    assert_eq!(mmu.read_u8(SB_REGISTER), 0xFF);
+    assert_eq!(mmu.read_u8(SC_REGISTER), 0x7F);
+    assert_eq!(mmu.read_u8(INTERRUPT_FLAG_REGISTER) & 0x08, 0x08);
+}
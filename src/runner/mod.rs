@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod tests;
+
+use gameboy::{GameBoy, CYCLES_PER_FRAME};
+use serial::SerialBuffer;
+
+/// JR -2
+pub const JR_SELF: [u8; 2] = [0x18, 0xFE];
+/// JP nn
+const JP: u8 = 0xC3;
+
+/// When to stop running without a display
+#[derive(Debug, Default)]
+pub struct StopConditions {
+    pub frames: Option<usize>,
+    pub cycles: Option<usize>,
+    /// Stop once the serial output contains the string
+    pub until_serial: Option<String>,
+    /// Stop at an instruction that jumps to itself
+    pub until_loop: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Stop {
+    FrameLimit,
+    CycleLimit,
+    Serial,
+    Loop,
+}
+
+/// The CPU is at JR -2 or JP to the same address,
+/// which test ROMs use to wait forever when they are done.
+fn in_infinite_loop(gameboy: &GameBoy) -> bool {
+    let pc = gameboy.cpu().pc;
+    let mmu = gameboy.mmu();
+    let opcode = [mmu.read_u8(pc), mmu.read_u8(pc.wrapping_add(1))];
+    opcode == JR_SELF || (opcode[0] == JP && mmu.read_u16(pc.wrapping_add(1)) == pc)
+}
+
+/// Runs the emulation until one of the `conditions` is met. Returns
+/// the reason and everything sent over the serial port.
+pub fn run(
+    gameboy: &mut GameBoy,
+    serial: &SerialBuffer,
+    conditions: &StopConditions,
+) -> (Stop, Vec<u8>) {
+    let mut output = vec![];
+    let mut frames = 0;
+    let mut frame_cycles = 0;
+    let stop = loop {
+        if conditions.frames.is_some_and(|limit| frames >= limit) {
+            break Stop::FrameLimit;
+        }
+        if conditions
+            .cycles
+            .is_some_and(|limit| gameboy.cycles() >= limit)
+        {
+            break Stop::CycleLimit;
+        }
+        if conditions.until_loop && in_infinite_loop(gameboy) {
+            break Stop::Loop;
+        }
+        frame_cycles += gameboy.step_instruction();
+        // Frames are counted in cycles, so that they
+        // advance while the LCD is off as well
+        if frame_cycles >= CYCLES_PER_FRAME {
+            frame_cycles -= CYCLES_PER_FRAME;
+            frames += 1;
+        }
+
+        let bytes = serial.take();
+        if !bytes.is_empty() {
+            output.extend(bytes);
+            if let Some(ref needle) = conditions.until_serial {
+                if String::from_utf8_lossy(&output).contains(needle.as_str()) {
+                    break Stop::Serial;
+                }
+            }
+        }
+    };
+    (stop, output)
+}
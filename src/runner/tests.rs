@@ -0,0 +1,80 @@
+use gameboy::tests::gameboy;
+use gameboy::{GameBoy, CYCLES_PER_FRAME};
+use runner::*;
+use serial::{CaptureLink, SerialBuffer};
+
+/// Runs `program` with the serial output captured
+fn capturing(program: &[u8]) -> (GameBoy, SerialBuffer) {
+    let serial = SerialBuffer::new();
+    let mut gameboy = gameboy(program);
+    gameboy.set_serial_link(Box::new(CaptureLink::new(serial.clone())));
+    (gameboy, serial)
+}
+
+#[test]
+fn test_until_loop() {
+    let (mut gameboy, serial) = capturing(&[0x00, 0x00, 0x18, 0xFE]);
+    let conditions = StopConditions {
+        until_loop: true,
+        ..Default::default()
+    };
+    assert_eq!(run(&mut gameboy, &serial, &conditions).0, Stop::Loop);
+    assert_eq!(gameboy.cpu().pc, 0x152);
+}
+
+#[test]
+fn test_until_loop_with_jp() {
+    // JP 0x0150
+    let (mut gameboy, serial) = capturing(&[0xC3, 0x50, 0x01]);
+    let conditions = StopConditions {
+        until_loop: true,
+        ..Default::default()
+    };
+    assert_eq!(run(&mut gameboy, &serial, &conditions).0, Stop::Loop);
+    assert_eq!(gameboy.cpu().pc, 0x150);
+}
+
+#[test]
+fn test_until_serial() {
+    let mut program = vec![];
+    for &c in b"ok" {
+        // LD A,c; LDH (01),A; LD A,81; LDH (02),A
+        program.extend_from_slice(&[0x3E, c, 0xE0, 0x01, 0x3E, 0x81, 0xE0, 0x02]);
+        // LDH A,(02); AND 80; JR NZ,-6
+        program.extend_from_slice(&[0xF0, 0x02, 0xE6, 0x80, 0x20, 0xFA]);
+    }
+    program.extend_from_slice(&JR_SELF);
+    let (mut gameboy, serial) = capturing(&program);
+    let conditions = StopConditions {
+        until_serial: Some("ok".to_string()),
+        frames: Some(10),
+        ..Default::default()
+    };
+    assert_eq!(
+        run(&mut gameboy, &serial, &conditions),
+        (Stop::Serial, b"ok".to_vec())
+    );
+}
+
+#[test]
+fn test_frame_limit() {
+    let (mut gameboy, serial) = capturing(&JR_SELF);
+    let conditions = StopConditions {
+        frames: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(run(&mut gameboy, &serial, &conditions).0, Stop::FrameLimit);
+    assert!((2 * CYCLES_PER_FRAME..2 * CYCLES_PER_FRAME + 12).contains(&gameboy.cycles()));
+}
+
+#[test]
+fn test_cycle_limit() {
+    let (mut gameboy, serial) = capturing(&JR_SELF);
+    let conditions = StopConditions {
+        cycles: Some(1000),
+        frames: Some(1000),
+        ..Default::default()
+    };
+    assert_eq!(run(&mut gameboy, &serial, &conditions).0, Stop::CycleLimit);
+    assert!((1000..1012).contains(&gameboy.cycles()));
+}
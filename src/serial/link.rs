@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::mem;
+use std::rc::Rc;
+
+/// The other end of the link cable.
+///
+/// The side that uses the internal clock drives the transfer and calls
+/// `transfer` once all 8 bits have been shifted. The side that waits for
+/// an external clock calls `poll` on every step until the byte arrives.
+pub trait SerialLink {
+    /// Sends `data` clocked by this side and returns the byte shifted in
+    /// from the other side, 0xFF if nothing answers.
+    fn transfer(&mut self, data: u8) -> u8;
+    /// Offers `data` to a transfer clocked by the other side.
+    /// Returns the received byte once the other side has sent one.
+    fn poll(&mut self, data: u8) -> Option<u8>;
+}
+
+/// No cable is connected. Transfers with the internal clock receive 0xFF
+/// and transfers with an external clock never finish.
+#[derive(Debug, Default)]
+pub struct Disconnected;
+impl SerialLink for Disconnected {
+    fn transfer(&mut self, _data: u8) -> u8 {
+        0xFF
+    }
+    fn poll(&mut self, _data: u8) -> Option<u8> {
+        None
+    }
+}
+
+/// Byte buffer that can be shared with a `CaptureLink`
+#[derive(Clone, Debug, Default)]
+pub struct SerialBuffer(Rc<RefCell<Vec<u8>>>);
+impl SerialBuffer {
+    pub fn new() -> SerialBuffer {
+        SerialBuffer::default()
+    }
+    /// Bytes written since the previous call
+    pub fn take(&self) -> Vec<u8> {
+        mem::take(&mut self.0.borrow_mut())
+    }
+}
+impl Write for SerialBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes the sent bytes to `W`, which is how test ROMs report
+/// their results. Behaves as `Disconnected` otherwise.
+pub struct CaptureLink<W: Write> {
+    out: W,
+}
+impl<W: Write> CaptureLink<W> {
+    pub fn new(out: W) -> CaptureLink<W> {
+        CaptureLink { out }
+    }
+}
+impl CaptureLink<io::Stdout> {
+    pub fn stdout() -> CaptureLink<io::Stdout> {
+        CaptureLink::new(io::stdout())
+    }
+}
+impl<W: Write> SerialLink for CaptureLink<W> {
+    fn transfer(&mut self, data: u8) -> u8 {
+        if let Err(e) = self.out.write_all(&[data]).and_then(|_| self.out.flush()) {
+            warn!("Could not capture serial output: {}", e);
+        }
+        0xFF
+    }
+    fn poll(&mut self, _data: u8) -> Option<u8> {
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct LoopbackPort {
+    /// SB of this side while it waits for an external clock
+    waiting: Option<u8>,
+    /// Byte sent by the other side, not yet picked up by `poll`
+    received: Option<u8>,
+}
+
+/// One end of a cable between two emulators running in the same process
+pub struct LoopbackLink {
+    ports: Rc<RefCell<[LoopbackPort; 2]>>,
+    side: usize,
+}
+impl LoopbackLink {
+    /// Both ends of the cable
+    pub fn pair() -> (LoopbackLink, LoopbackLink) {
+        let ports = Rc::new(RefCell::new(Default::default()));
+        (
+            LoopbackLink {
+                ports: ports.clone(),
+                side: 0,
+            },
+            LoopbackLink { ports, side: 1 },
+        )
+    }
+}
+impl SerialLink for LoopbackLink {
+    fn transfer(&mut self, data: u8) -> u8 {
+        let mut ports = self.ports.borrow_mut();
+        let other = &mut ports[1 - self.side];
+        match other.waiting.take() {
+            Some(reply) => {
+                other.received = Some(data);
+                reply
+            }
+            None => 0xFF,
+        }
+    }
+    fn poll(&mut self, data: u8) -> Option<u8> {
+        let mut ports = self.ports.borrow_mut();
+        let port = &mut ports[self.side];
+        match port.received.take() {
+            Some(byte) => Some(byte),
+            None => {
+                port.waiting = Some(data);
+                None
+            }
+        }
+    }
+}
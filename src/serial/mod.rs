@@ -0,0 +1,108 @@
+mod link;
+#[cfg(not(target_os = "unknown"))]
+mod tcp;
+#[cfg(test)]
+mod tests;
+
+pub use self::link::{CaptureLink, Disconnected, LoopbackLink, SerialBuffer, SerialLink};
+#[cfg(not(target_os = "unknown"))]
+pub use self::tcp::TcpLink;
+
+pub const SB_REGISTER: u16 = 0xFF01;
+pub const SC_REGISTER: u16 = 0xFF02;
+
+const SC_TRANSFER: u8 = 0x80;
+const SC_INTERNAL_CLOCK: u8 = 0x01;
+/// The internal clock runs at 8192 Hz
+const CYCLES_PER_BIT: usize = 512;
+pub const CYCLES_PER_TRANSFER: usize = 8 * CYCLES_PER_BIT;
+
+/// SB and SC registers of the serial port.
+///
+/// Setting bit 7 of SC starts a transfer of SB. With the internal clock
+/// the transfer takes 8 bits at 8192 Hz, with an external clock it waits
+/// until the other side of the link sends a byte. SB is replaced with the
+/// received byte when the transfer finishes.
+pub struct Serial {
+    sb: u8,
+    sc: u8,
+    /// Cycles since the internal clock transfer was started
+    cycles: usize,
+    link: Box<dyn SerialLink>,
+}
+
+impl Default for Serial {
+    fn default() -> Serial {
+        Serial::new(Box::new(Disconnected))
+    }
+}
+
+impl Serial {
+    pub fn new(link: Box<dyn SerialLink>) -> Serial {
+        Serial {
+            sb: 0,
+            sc: 0,
+            cycles: 0,
+            link,
+        }
+    }
+
+    pub fn set_link(&mut self, link: Box<dyn SerialLink>) {
+        self.link = link;
+    }
+
+    /// A transfer is running or waiting for an external clock
+    #[inline]
+    pub fn transferring(&self) -> bool {
+        self.sc & SC_TRANSFER != 0
+    }
+
+    fn finish(&mut self, received: u8) {
+        self.sb = received;
+        self.sc &= !SC_TRANSFER;
+    }
+
+    /// Advances the serial port by `cycles` clock cycles,
+    /// returns true if the serial interrupt was requested.
+    pub fn step(&mut self, cycles: usize) -> bool {
+        if !self.transferring() {
+            return false;
+        }
+        if self.sc & SC_INTERNAL_CLOCK != 0 {
+            self.cycles += cycles;
+            if self.cycles < CYCLES_PER_TRANSFER {
+                return false;
+            }
+            let received = self.link.transfer(self.sb);
+            self.finish(received);
+            true
+        } else {
+            match self.link.poll(self.sb) {
+                Some(received) => {
+                    self.finish(received);
+                    true
+                }
+                None => false,
+            }
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            SB_REGISTER => self.sb,
+            SC_REGISTER => self.sc | 0x7E,
+            _ => unreachable!("Serial read from {:04X}", addr),
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            SB_REGISTER => self.sb = value,
+            SC_REGISTER => {
+                self.sc = value & (SC_TRANSFER | SC_INTERNAL_CLOCK);
+                self.cycles = 0;
+            }
+            _ => unreachable!("Serial write to {:04X}", addr),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::SerialLink;
+
+const DATA: u8 = 0x01;
+const REPLY: u8 = 0x02;
+const CANCEL: u8 = 0x03;
+const READY: u8 = 0x04;
+/// How long a transfer with the internal clock waits for the other side
+const REPLY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Three-byte messages exchanged over the connection. Every transfer
+/// clocked by the sender has a sequence number, so that answers to
+/// transfers that timed out can be told apart.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Message {
+    /// Byte of a transfer clocked by the sender
+    Data(u8, u8),
+    /// Answer to `Data` with the byte shifted out by the receiver
+    Reply(u8, u8),
+    /// The transfer timed out and must not be answered anymore
+    Cancel(u8),
+    /// The sender is waiting for an external clock
+    Ready,
+}
+impl Message {
+    fn encode(self) -> [u8; 3] {
+        match self {
+            Message::Data(seq, byte) => [DATA, seq, byte],
+            Message::Reply(seq, byte) => [REPLY, seq, byte],
+            Message::Cancel(seq) => [CANCEL, seq, 0],
+            Message::Ready => [READY, 0, 0],
+        }
+    }
+    fn decode(buf: [u8; 3]) -> Option<Message> {
+        match buf[0] {
+            DATA => Some(Message::Data(buf[1], buf[2])),
+            REPLY => Some(Message::Reply(buf[1], buf[2])),
+            CANCEL => Some(Message::Cancel(buf[1])),
+            READY => Some(Message::Ready),
+            _ => None,
+        }
+    }
+}
+
+/// Link cable to another emulator process over TCP.
+///
+/// The emulators are kept in lock-step one byte at a time: a transfer
+/// with the internal clock waits until the other side has answered it
+/// from a transfer waiting for an external clock. If the other side
+/// doesn't answer within `REPLY_TIMEOUT`, or both sides clock a transfer
+/// at the same time, the transfer receives 0xFF. A transfer that timed
+/// out is cancelled, so the other side doesn't receive it when it starts
+/// waiting later. Once the connection is closed the link behaves as if
+/// the cable was unplugged.
+///
+/// Waiting stalls the emulation, so after a timeout transfers receive
+/// 0xFF right away until the other side sends something, for example
+/// when it starts waiting for an external clock. A game that clocks
+/// transfers while the other side isn't listening is slowed down by
+/// one `REPLY_TIMEOUT` each time the other side stops listening.
+pub struct TcpLink {
+    stream: TcpStream,
+    messages: Receiver<Message>,
+    connected: bool,
+    /// Sequence number of the next transfer with the internal clock
+    seq: u8,
+    /// The other side has sent something since the last timeout
+    peer_active: bool,
+    /// `Ready` was sent for the transfer waiting for an external clock
+    ready_sent: bool,
+}
+
+impl TcpLink {
+    /// Waits for the other emulator to connect to `addr`
+    pub fn listen<A: ToSocketAddrs>(addr: A) -> io::Result<TcpLink> {
+        TcpLink::accept(&TcpListener::bind(addr)?)
+    }
+
+    pub fn accept(listener: &TcpListener) -> io::Result<TcpLink> {
+        let (stream, peer) = listener.accept()?;
+        info!("Link cable connected to {}", peer);
+        TcpLink::new(stream)
+    }
+
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<TcpLink> {
+        let stream = TcpStream::connect(addr)?;
+        info!("Link cable connected to {}", stream.peer_addr()?);
+        TcpLink::new(stream)
+    }
+
+    fn new(stream: TcpStream) -> io::Result<TcpLink> {
+        stream.set_nodelay(true)?;
+        let mut reader = stream.try_clone()?;
+        let (sender, messages) = mpsc::channel();
+        // Messages are read on a thread of their own so that waiting
+        // for an external clock doesn't need a system call on every step
+        thread::spawn(move || {
+            let mut buf = [0; 3];
+            while reader.read_exact(&mut buf).is_ok() {
+                match Message::decode(buf) {
+                    Some(message) => {
+                        if sender.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    None => {
+                        warn!("Invalid link cable message {:02X?}", buf);
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(TcpLink {
+            stream,
+            messages,
+            connected: true,
+            seq: 0,
+            peer_active: true,
+            ready_sent: false,
+        })
+    }
+
+    fn send(&mut self, message: Message) {
+        if let Err(e) = self.stream.write_all(&message.encode()) {
+            self.disconnect(e);
+        }
+    }
+
+    /// Receives the next message, waiting at most `timeout`
+    fn receive(&mut self, timeout: Duration) -> Result<Message, RecvTimeoutError> {
+        let message = self.messages.recv_timeout(timeout)?;
+        self.peer_active = true;
+        Ok(message)
+    }
+
+    fn disconnect<E: ToString>(&mut self, reason: E) {
+        if self.connected {
+            warn!("Link cable disconnected: {}", reason.to_string());
+            self.connected = false;
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl SerialLink for TcpLink {
+    fn transfer(&mut self, data: u8) -> u8 {
+        let seq = self.seq;
+        self.seq = self.seq.wrapping_add(1);
+        let mut deadline = None;
+        while self.connected {
+            if deadline.is_none() && self.peer_active {
+                self.send(Message::Data(seq, data));
+                deadline = Some(Instant::now() + REPLY_TIMEOUT);
+            }
+            // Until the other side is active again only
+            // the messages already received are handled
+            let timeout = deadline.map_or(Duration::ZERO, |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            });
+            match self.receive(timeout) {
+                Ok(Message::Reply(reply, byte)) if reply == seq => return byte,
+                // Answers to transfers that timed out only
+                // tell that the other side is active again
+                Ok(Message::Reply(..)) | Ok(Message::Cancel(_)) | Ok(Message::Ready) => {}
+                // The other side clocked a transfer as well
+                Ok(Message::Data(other, _)) => self.send(Message::Reply(other, 0xFF)),
+                Err(RecvTimeoutError::Timeout) => {
+                    if deadline.is_some() {
+                        self.send(Message::Cancel(seq));
+                        self.peer_active = false;
+                    }
+                    break;
+                }
+                Err(RecvTimeoutError::Disconnected) => self.disconnect("connection closed"),
+            }
+        }
+        0xFF
+    }
+
+    fn poll(&mut self, data: u8) -> Option<u8> {
+        // Everything received is looked at before answering,
+        // so that a transfer is not answered after its cancel
+        let mut pending = None;
+        loop {
+            match self.receive(Duration::ZERO) {
+                Ok(Message::Data(seq, byte)) => pending = Some((seq, byte)),
+                Ok(Message::Cancel(seq)) => {
+                    if pending.is_some_and(|(pending, _)| pending == seq) {
+                        pending = None;
+                    }
+                    // The other side gave up waiting for
+                    // this side, it has to be told again
+                    self.ready_sent = false;
+                }
+                Ok(Message::Reply(..)) | Ok(Message::Ready) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.disconnect("connection closed");
+                    return None;
+                }
+            }
+        }
+        match pending {
+            Some((seq, byte)) => {
+                self.send(Message::Reply(seq, data));
+                self.ready_sent = false;
+                Some(byte)
+            }
+            None => {
+                if !self.ready_sent && self.connected {
+                    self.send(Message::Ready);
+                    self.ready_sent = true;
+                }
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use serial::*;
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Starts a transfer of `data` with the given clock
+fn start(serial: &mut Serial, data: u8, internal_clock: bool) {
+    serial.write(SB_REGISTER, data);
+    serial.write(SC_REGISTER, if internal_clock { 0x81 } else { 0x80 });
+}
+
+#[test]
+fn test_unused_bits() {
+    let mut serial = Serial::default();
+    assert_eq!(serial.read(SC_REGISTER), 0x7E);
+    serial.write(SC_REGISTER, 0xFF);
+    assert_eq!(serial.read(SC_REGISTER), 0xFF);
+}
+
+#[test]
+fn test_internal_clock_timing() {
+    let mut serial = Serial::default();
+    start(&mut serial, 0x42, true);
+    assert!(!serial.step(CYCLES_PER_TRANSFER - 1));
+    assert!(serial.transferring());
+    assert_eq!(serial.read(SB_REGISTER), 0x42);
+    assert!(serial.step(1));
+    assert!(!serial.transferring());
+    assert_eq!(serial.read(SB_REGISTER), 0xFF);
+    assert_eq!(serial.read(SC_REGISTER), 0x7F);
+    assert!(!serial.step(CYCLES_PER_TRANSFER));
+}
+
+#[test]
+fn test_restart_resets_timing() {
+    let mut serial = Serial::default();
+    start(&mut serial, 0x42, true);
+    serial.step(CYCLES_PER_TRANSFER - 1);
+    start(&mut serial, 0x42, true);
+    assert!(!serial.step(CYCLES_PER_TRANSFER - 1));
+    assert!(serial.step(1));
+}
+
+#[test]
+fn test_external_clock_disconnected() {
+    let mut serial = Serial::default();
+    start(&mut serial, 0x42, false);
+    assert!(!serial.step(100 * CYCLES_PER_TRANSFER));
+    assert!(serial.transferring());
+    assert_eq!(serial.read(SB_REGISTER), 0x42);
+}
+
+#[test]
+fn test_capture() {
+    let output = SerialBuffer::new();
+    let mut serial = Serial::new(Box::new(CaptureLink::new(output.clone())));
+    for &c in b"Passed" {
+        start(&mut serial, c, true);
+        serial.step(CYCLES_PER_TRANSFER);
+    }
+    assert_eq!(output.take(), b"Passed");
+    assert!(output.take().is_empty());
+}
+
+#[test]
+fn test_capture_ignores_external_clock() {
+    let output = SerialBuffer::new();
+    let mut serial = Serial::new(Box::new(CaptureLink::new(output.clone())));
+    start(&mut serial, b'x', false);
+    assert!(!serial.step(CYCLES_PER_TRANSFER));
+    assert!(output.take().is_empty());
+}
+
+#[test]
+fn test_loopback() {
+    let (a, b) = LoopbackLink::pair();
+    let mut master = Serial::new(Box::new(a));
+    let mut slave = Serial::new(Box::new(b));
+    start(&mut slave, 0x55, false);
+    start(&mut master, 0xAA, true);
+    for _ in 0..CYCLES_PER_TRANSFER / 4 - 1 {
+        assert!(!slave.step(4));
+        assert!(!master.step(4));
+    }
+    assert!(!slave.step(4));
+    assert!(master.step(4));
+    assert_eq!(master.read(SB_REGISTER), 0x55);
+    assert!(slave.step(4));
+    assert_eq!(slave.read(SB_REGISTER), 0xAA);
+    assert!(!slave.transferring());
+}
+
+#[test]
+fn test_loopback_slave_not_ready() {
+    let (a, b) = LoopbackLink::pair();
+    let mut master = Serial::new(Box::new(a));
+    let mut slave = Serial::new(Box::new(b));
+    start(&mut master, 0xAA, true);
+    assert!(master.step(CYCLES_PER_TRANSFER));
+    assert_eq!(master.read(SB_REGISTER), 0xFF);
+    start(&mut slave, 0x55, false);
+    assert!(!slave.step(CYCLES_PER_TRANSFER));
+    assert_eq!(slave.read(SB_REGISTER), 0x55);
+}
+
+#[test]
+fn test_loopback_both_directions() {
+    let (a, b) = LoopbackLink::pair();
+    let mut left = Serial::new(Box::new(a));
+    let mut right = Serial::new(Box::new(b));
+    start(&mut left, 0x01, false);
+    left.step(4);
+    start(&mut right, 0x02, true);
+    assert!(right.step(CYCLES_PER_TRANSFER));
+    assert!(left.step(4));
+    assert_eq!(left.read(SB_REGISTER), 0x02);
+    assert_eq!(right.read(SB_REGISTER), 0x01);
+}
+
+/// Runs a transfer of `data` on its own thread, returns the received byte
+fn tcp_transfer(link: TcpLink, data: u8, internal_clock: bool) -> thread::JoinHandle<u8> {
+    thread::spawn(move || {
+        let mut serial = Serial::new(Box::new(link));
+        start(&mut serial, data, internal_clock);
+        while !serial.step(4) {}
+        serial.read(SB_REGISTER)
+    })
+}
+
+fn tcp_pair() -> (TcpLink, TcpLink) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = thread::spawn(move || TcpLink::connect(addr).unwrap());
+    let server = TcpLink::accept(&listener).unwrap();
+    (server, client.join().unwrap())
+}
+
+#[test]
+fn test_tcp() {
+    let (server, client) = tcp_pair();
+    let master = tcp_transfer(server, 0xAA, true);
+    let slave = tcp_transfer(client, 0x55, false);
+    assert_eq!(master.join().unwrap(), 0x55);
+    assert_eq!(slave.join().unwrap(), 0xAA);
+}
+
+#[test]
+fn test_tcp_master_waits_for_slave() {
+    let (server, client) = tcp_pair();
+    let master = tcp_transfer(server, 0xAA, true);
+    thread::sleep(Duration::from_millis(20));
+    let slave = tcp_transfer(client, 0x55, false);
+    assert_eq!(master.join().unwrap(), 0x55);
+    assert_eq!(slave.join().unwrap(), 0xAA);
+}
+
+#[test]
+fn test_tcp_both_masters() {
+    let (server, client) = tcp_pair();
+    let left = tcp_transfer(server, 0xAA, true);
+    let right = tcp_transfer(client, 0x55, true);
+    assert_eq!(left.join().unwrap(), 0xFF);
+    assert_eq!(right.join().unwrap(), 0xFF);
+}
+
+#[test]
+fn test_tcp_disconnected() {
+    let (server, client) = tcp_pair();
+    drop(client);
+    assert_eq!(tcp_transfer(server, 0xAA, true).join().unwrap(), 0xFF);
+}
+
+#[test]
+fn test_tcp_slave_not_ready() {
+    let (mut master, client) = tcp_pair();
+    let mut slave = Serial::new(Box::new(client));
+    assert_eq!(master.transfer(0xAA), 0xFF);
+    // The transfer that timed out is cancelled, a slave
+    // starting to wait later doesn't receive it
+    thread::sleep(Duration::from_millis(20));
+    start(&mut slave, 0x55, false);
+    assert!(!slave.step(CYCLES_PER_TRANSFER));
+    assert_eq!(slave.read(SB_REGISTER), 0x55);
+
+    // The next transfer is answered by the waiting slave
+    thread::sleep(Duration::from_millis(20));
+    let master = thread::spawn(move || master.transfer(0xBB));
+    while !slave.step(4) {}
+    assert_eq!(slave.read(SB_REGISTER), 0xBB);
+    assert_eq!(master.join().unwrap(), 0x55);
+}
+
+#[test]
+fn test_tcp_stops_waiting_for_idle_slave() {
+    let (mut master, _slave) = tcp_pair();
+    let start = Instant::now();
+    assert_eq!(master.transfer(0xAA), 0xFF);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    // Until the slave sends something the
+    // transfers don't wait for it anymore
+    let start = Instant::now();
+    for _ in 0..10 {
+        assert_eq!(master.transfer(0xAA), 0xFF);
+    }
+    assert!(start.elapsed() < Duration::from_millis(50));
+}
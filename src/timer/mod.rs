@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod tests;
+
+pub const DIV_REGISTER: u16 = 0xFF04;
+pub const TIMA_REGISTER: u16 = 0xFF05;
+pub const TMA_REGISTER: u16 = 0xFF06;
+pub const TAC_REGISTER: u16 = 0xFF07;
+
+const TAC_ENABLE: u8 = 0x04;
+const CYCLES_PER_TICK: usize = 4;
+
+/// DIV, TIMA, TMA and TAC registers.
+///
+/// DIV is the upper byte of a 16-bit counter that is incremented
+/// on every clock cycle. TIMA is incremented on the falling edge of
+/// the counter bit selected by TAC, which is why resetting DIV or
+/// changing TAC can increment TIMA as a side effect.
+#[derive(Debug, Default)]
+pub struct Timer {
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    /// TIMA overflowed during the previous cycle and reads as 0x00,
+    /// it will be reloaded from TMA on the next cycle
+    overflow: bool,
+    /// TIMA was reloaded from TMA during the current cycle
+    reloaded: bool,
+    /// Cycles that did not make up a whole machine cycle yet
+    remainder: usize,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer::default()
+    }
+
+    /// Counter bit which drives TIMA with the frequency selected in TAC
+    #[inline]
+    fn selected_bit(&self) -> u16 {
+        match self.tac & 0x03 {
+            0b00 => 1 << 9, // 4096 Hz
+            0b01 => 1 << 3, // 262144 Hz
+            0b10 => 1 << 5, // 65536 Hz
+            _ => 1 << 7,    // 16384 Hz
+        }
+    }
+
+    #[inline]
+    fn signal(&self) -> bool {
+        self.tac & TAC_ENABLE != 0 && self.counter & self.selected_bit() != 0
+    }
+
+    fn increment_tima(&mut self) {
+        let (tima, overflow) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        self.overflow = overflow;
+    }
+
+    /// Changes the counter and TAC, incrementing TIMA if the
+    /// selected signal falls as a result
+    fn update<F: FnOnce(&mut Timer)>(&mut self, change: F) {
+        let before = self.signal();
+        change(self);
+        if before && !self.signal() {
+            self.increment_tima();
+        }
+    }
+
+    /// Advances the timer by one machine cycle,
+    /// returns true if the timer interrupt was requested.
+    fn tick(&mut self) -> bool {
+        self.reloaded = false;
+        let interrupt = if self.overflow {
+            self.overflow = false;
+            self.reloaded = true;
+            self.tima = self.tma;
+            true
+        } else {
+            false
+        };
+        self.update(|t| t.counter = t.counter.wrapping_add(CYCLES_PER_TICK as u16));
+        interrupt
+    }
+
+    /// Advances the timer by `cycles` clock cycles,
+    /// returns true if the timer interrupt was requested.
+    pub fn step(&mut self, cycles: usize) -> bool {
+        let cycles = self.remainder + cycles;
+        self.remainder = cycles % CYCLES_PER_TICK;
+        let mut interrupt = false;
+        for _ in 0..cycles / CYCLES_PER_TICK {
+            interrupt |= self.tick();
+        }
+        interrupt
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            DIV_REGISTER => (self.counter >> 8) as u8,
+            TIMA_REGISTER => self.tima,
+            TMA_REGISTER => self.tma,
+            TAC_REGISTER => self.tac | 0xF8,
+            _ => unreachable!("Timer read from {:04X}", addr),
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            DIV_REGISTER => self.update(|t| t.counter = 0),
+            TIMA_REGISTER => {
+                // A write during the overflow cycle cancels the reload,
+                // a write during the reload cycle is ignored
+                if !self.reloaded {
+                    self.tima = value;
+                    self.overflow = false;
+                }
+            }
+            TMA_REGISTER => {
+                self.tma = value;
+                if self.reloaded {
+                    self.tima = value;
+                }
+            }
+            TAC_REGISTER => self.update(|t| t.tac = value & 0x07),
+            _ => unreachable!("Timer write to {:04X}", addr),
+        }
+    }
+}
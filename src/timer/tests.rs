@@ -0,0 +1,120 @@
+use timer::*;
+
+fn timer(tac: u8) -> Timer {
+    let mut timer = Timer::new();
+    timer.write(TAC_REGISTER, tac);
+    timer
+}
+
+#[test]
+fn test_div_increments() {
+    let mut timer = Timer::new();
+    assert_eq!(timer.read(DIV_REGISTER), 0);
+    timer.step(252);
+    assert_eq!(timer.read(DIV_REGISTER), 0);
+    timer.step(4);
+    assert_eq!(timer.read(DIV_REGISTER), 1);
+    timer.step(256 * 255);
+    assert_eq!(timer.read(DIV_REGISTER), 0);
+}
+
+#[test]
+fn test_div_write_resets() {
+    let mut timer = Timer::new();
+    timer.step(1000);
+    assert_ne!(timer.read(DIV_REGISTER), 0);
+    timer.write(DIV_REGISTER, 0x55);
+    assert_eq!(timer.read(DIV_REGISTER), 0);
+}
+
+#[test]
+fn test_tima_frequencies() {
+    for &(tac, period) in &[(0x04, 1024), (0x05, 16), (0x06, 64), (0x07, 256)] {
+        let mut timer = timer(tac);
+        timer.step(period - 4);
+        assert_eq!(timer.read(TIMA_REGISTER), 0, "TAC {:02X}", tac);
+        timer.step(4);
+        assert_eq!(timer.read(TIMA_REGISTER), 1, "TAC {:02X}", tac);
+        timer.step(period * 9);
+        assert_eq!(timer.read(TIMA_REGISTER), 10, "TAC {:02X}", tac);
+    }
+}
+
+#[test]
+fn test_tima_disabled() {
+    let mut timer = timer(0x01);
+    timer.step(4096);
+    assert_eq!(timer.read(TIMA_REGISTER), 0);
+    assert_eq!(timer.read(TAC_REGISTER), 0xF9);
+}
+
+#[test]
+fn test_tima_partial_cycles() {
+    let mut timer = timer(0x05);
+    for _ in 0..8 {
+        timer.step(2);
+    }
+    assert_eq!(timer.read(TIMA_REGISTER), 1);
+}
+
+#[test]
+fn test_tima_overflow_reload_delay() {
+    let mut timer = timer(0x05);
+    timer.write(TMA_REGISTER, 0xF0);
+    timer.write(TIMA_REGISTER, 0xFF);
+
+    // TIMA reads 0x00 for one cycle before it is reloaded
+    assert!(!timer.step(16));
+    assert_eq!(timer.read(TIMA_REGISTER), 0x00);
+    assert!(timer.step(4));
+    assert_eq!(timer.read(TIMA_REGISTER), 0xF0);
+    assert!(!timer.step(4));
+}
+
+#[test]
+fn test_tima_write_cancels_reload() {
+    let mut timer = timer(0x05);
+    timer.write(TMA_REGISTER, 0xF0);
+    timer.write(TIMA_REGISTER, 0xFF);
+    timer.step(16);
+    timer.write(TIMA_REGISTER, 0x10);
+    assert!(!timer.step(4));
+    assert_eq!(timer.read(TIMA_REGISTER), 0x10);
+}
+
+#[test]
+fn test_writes_during_reload_cycle() {
+    let mut timer = timer(0x05);
+    timer.write(TMA_REGISTER, 0xF0);
+    timer.write(TIMA_REGISTER, 0xFF);
+    timer.step(20);
+
+    // TIMA writes are ignored and TMA writes go through to TIMA
+    timer.write(TIMA_REGISTER, 0x10);
+    assert_eq!(timer.read(TIMA_REGISTER), 0xF0);
+    timer.write(TMA_REGISTER, 0x20);
+    assert_eq!(timer.read(TIMA_REGISTER), 0x20);
+}
+
+#[test]
+fn test_div_write_glitch() {
+    // Bit 3 of the counter is set, resetting it is a falling edge
+    let mut timer = timer(0x05);
+    timer.step(8);
+    assert_eq!(timer.read(TIMA_REGISTER), 0);
+    timer.write(DIV_REGISTER, 0);
+    assert_eq!(timer.read(TIMA_REGISTER), 1);
+
+    // Bit 3 is not set, no increment
+    timer.step(4);
+    timer.write(DIV_REGISTER, 0);
+    assert_eq!(timer.read(TIMA_REGISTER), 1);
+}
+
+#[test]
+fn test_tac_write_glitch() {
+    let mut timer = timer(0x05);
+    timer.step(8);
+    timer.write(TAC_REGISTER, 0x01);
+    assert_eq!(timer.read(TIMA_REGISTER), 1);
+}
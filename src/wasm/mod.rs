@@ -5,45 +5,95 @@ mod wasmdisplay;
 #[macro_use]
 mod wasmlog;
 
-use cpu::Cpu;
-use gpu::Gpu;
-use mmu::Mmu;
+use display::{Display, Palette};
+use gameboy::GameBoy;
+use joypad::Button;
 
 #[wasm_bindgen]
 pub struct Emulator {
-    cpu: Cpu,
-    gpu: Gpu,
-    mmu: Mmu,
+    gameboy: GameBoy,
+    /// ROM of the loaded cartridge, kept for resetting
+    rom: Vec<u8>,
     display: wasmdisplay::WasmDisplay,
 }
 
+fn to_js<E: ToString>(e: E) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+fn power_on(rom: &[u8]) -> Result<GameBoy, JsValue> {
+    GameBoy::builder()
+        .rom_data(rom.to_vec())
+        .build()
+        .map_err(to_js)
+}
+
+/// Restarts the loaded cartridge, keeping its battery-backed memory
 #[wasm_bindgen]
-pub fn reset(emu: &mut Emulator) {
-    emu.cpu.reset();
+pub fn reset(emu: &mut Emulator) -> Result<(), JsValue> {
+    let save_data = emu.gameboy.save_data();
+    emu.gameboy = power_on(&emu.rom)?;
+    if let Some(data) = save_data {
+        emu.gameboy.load_save_data(&data);
+    }
+    Ok(())
 }
 
 #[wasm_bindgen]
 pub fn run_until_redraw(emu: &mut Emulator) -> bool {
-    while !emu.display.is_dirty() {
-        emu.cpu.step(&mut emu.mmu);
-        emu.gpu
-            .step(&mut emu.display, &mut emu.mmu, emu.cpu.cycles());
-    }
+    emu.gameboy.run_frame();
+    emu.display.render_frame(emu.gameboy.framebuffer());
     true
 }
 
 #[wasm_bindgen]
 pub fn display_buffer(emu: &Emulator) -> Vec<u8> {
-    emu.display
-        .buffer()
-        .into_iter()
-        .flat_map(|&pixel| vec![pixel, pixel, pixel, 255].into_iter())
-        .collect()
+    emu.display.buffer()
 }
 
+/// Switches between the "grey" and "green" color schemes
 #[wasm_bindgen]
-pub fn load_cartridge_data(emu: &mut Emulator, data: &[u8]) {
-    emu.mmu.load_cartridge_data(data);
+pub fn set_palette(emu: &mut Emulator, name: &str) -> Result<(), JsValue> {
+    let palette = match name {
+        "grey" => Palette::GREY,
+        "green" => Palette::GREEN,
+        _ => return Err(JsValue::from_str(&format!("Unknown palette {}", name))),
+    };
+    emu.display.set_palette(palette);
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn load_cartridge_data(emu: &mut Emulator, data: &[u8]) -> Result<(), JsValue> {
+    emu.gameboy = power_on(data)?;
+    emu.rom = data.to_vec();
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn has_battery(emu: &Emulator) -> bool {
+    emu.gameboy.has_battery()
+}
+
+/// Battery-backed cartridge memory, empty if the cartridge has no battery
+#[wasm_bindgen]
+pub fn export_save_data(emu: &mut Emulator) -> Vec<u8> {
+    emu.gameboy.save_data().unwrap_or_default()
+}
+
+#[wasm_bindgen]
+pub fn import_save_data(emu: &mut Emulator, data: &[u8]) {
+    emu.gameboy.load_save_data(data);
+}
+
+#[wasm_bindgen]
+pub fn press_button(emu: &mut Emulator, button: Button) {
+    emu.gameboy.press(button);
+}
+
+#[wasm_bindgen]
+pub fn release_button(emu: &mut Emulator, button: Button) {
+    emu.gameboy.release(button);
 }
 
 #[wasm_bindgen]
@@ -52,7 +102,7 @@ pub fn should_redraw_display(emu: &mut Emulator) -> bool {
 }
 
 fn get_display() -> wasmdisplay::WasmDisplay {
-    wasmdisplay::WasmDisplay::new()
+    wasmdisplay::WasmDisplay::new(Palette::default())
 }
 
 #[wasm_bindgen]
@@ -60,15 +110,11 @@ pub fn get_emulator(rom: &str) -> Emulator {
     wasm_logger::init(wasm_logger::Config::new(log::Level::Info));
     info!("Starting {}", rom);
 
-    let cpu = Cpu::new();
-    let gpu = Gpu::new();
-    let mmu = Mmu::new();
-    let display = get_display();
-
     Emulator {
-        cpu,
-        gpu,
-        mmu,
-        display,
+        gameboy: GameBoy::builder()
+            .build()
+            .expect("GameBoy without a cartridge"),
+        rom: vec![],
+        display: get_display(),
     }
 }
@@ -1,20 +1,31 @@
-use display::Display;
+use display::{Display, Palette};
+use gpu::{SCREEN_HEIGHT, SCREEN_WIDTH};
 
 pub struct WasmDisplay {
     dirty: bool,
-    buffer: Box<[u8]>,
-    output_buf: [u8; 160 * 144],
+    output_buf: [u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+    palette: Palette,
 }
 impl WasmDisplay {
-    pub fn new() -> WasmDisplay {
+    pub fn new(palette: Palette) -> WasmDisplay {
         WasmDisplay {
-            buffer: vec![255u8; 256 * 256].into_boxed_slice(),
-            output_buf: [255u8; 160 * 144],
+            output_buf: [0u8; SCREEN_WIDTH * SCREEN_HEIGHT],
             dirty: false,
+            palette,
         }
     }
-    pub fn buffer(&self) -> [u8; 160 * 144] {
+    /// The frame as RGBA pixels
+    pub fn buffer(&self) -> Vec<u8> {
         self.output_buf
+            .iter()
+            .flat_map(|&shade| {
+                let [r, g, b] = self.palette.rgb(shade);
+                vec![r, g, b, 255].into_iter()
+            })
+            .collect()
+    }
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
     }
     pub fn is_dirty(&mut self) -> bool {
         let ret = self.dirty;
@@ -23,30 +34,8 @@ impl WasmDisplay {
     }
 }
 impl Display for WasmDisplay {
-    fn write_scanline(&mut self, y: u8, data: &[u8]) {
-        for (i, pixel) in data.into_iter().enumerate() {
-            // TODO: Palettes
-            let pixel = match pixel {
-                0 => 255u8,
-                1 => 192u8,
-                2 => 96u8,
-                3 => 0u8,
-                data => unreachable!("Invalid pixel data: {}", data),
-            };
-            self.buffer[256 * y as usize + i] = pixel;
-        }
-    }
-    fn render_framebuffer(&mut self, scrollx: u8, scrolly: u8) {
-        let mut i = 0;
-        for y in 0u8..144 {
-            for x in 0u8..160 {
-                let scrolledx = x.wrapping_add(scrollx);
-                let scrolledy = y.wrapping_add(scrolly);
-                let val = self.buffer[scrolledy as usize * 256 + scrolledx as usize];
-                self.output_buf[i] = val;
-                i += 1;
-            }
-        }
+    fn render_frame(&mut self, frame: &[u8]) {
+        self.output_buf.copy_from_slice(frame);
         self.dirty = true;
     }
 }
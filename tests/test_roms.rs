@@ -0,0 +1,132 @@
+//! Runs Blargg and Mooneye test ROMs and prints a table of the results.
+//!
+//! The ROMs are searched recursively from the directory in the
+//! `TEST_ROMS` environment variable, `test-roms` by default. The test
+//! is skipped if the directory does not exist. Failing ROMs don't fail
+//! the test, the table is there to track the accuracy of the emulator:
+//!
+//!     TEST_ROMS=~/gb-test-roms cargo test --release --test test_roms -- --nocapture
+//!
+//! Each ROM is run until it reports a result or the cycle budget in
+//! `TEST_ROMS_CYCLES` runs out, 60 emulated seconds by default.
+
+extern crate gameboy;
+
+use gameboy::gameboy::CLOCK_SPEED;
+use gameboy::serial::{CaptureLink, SerialBuffer};
+use gameboy::GameBoy;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// LD B,B is used by Mooneye tests as a breakpoint when they finish
+const LD_B_B: u8 = 0x40;
+/// B, C, D, E, H and L of a passed Mooneye test
+const MOONEYE_PASSED: [u8; 6] = [3, 5, 8, 13, 21, 34];
+const MOONEYE_FAILED: [u8; 6] = [0x42; 6];
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Passed,
+    Failed,
+    Timeout,
+    Error(String),
+}
+impl fmt::Display for Outcome {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Passed => write!(fmt, "PASS"),
+            Outcome::Failed => write!(fmt, "FAIL"),
+            Outcome::Timeout => write!(fmt, "TIMEOUT"),
+            Outcome::Error(e) => write!(fmt, "ERROR: {}", e),
+        }
+    }
+}
+
+fn find_roms(dir: &Path, roms: &mut Vec<PathBuf>) {
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(_) => return,
+    };
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            find_roms(&path, roms);
+        } else if path.extension().is_some_and(|ext| ext == "gb") {
+            roms.push(path);
+        }
+    }
+}
+
+fn run(rom: &Path, budget: usize) -> (Outcome, usize) {
+    let output = SerialBuffer::new();
+    let mut gameboy = match GameBoy::builder()
+        .rom(rom)
+        .serial_link(Box::new(CaptureLink::new(output.clone())))
+        .build()
+    {
+        Ok(gameboy) => gameboy,
+        Err(e) => return (Outcome::Error(e.to_string()), 0),
+    };
+    let mut serial = String::new();
+
+    while gameboy.cycles() < budget {
+        let pc = gameboy.cpu().pc;
+        if gameboy.mmu().read_u8(pc) == LD_B_B {
+            let r = gameboy.cpu().registers();
+            let signature = [r.b, r.c, r.d, r.e, r.h, r.l];
+            if signature == MOONEYE_PASSED {
+                return (Outcome::Passed, gameboy.cycles());
+            }
+            if signature == MOONEYE_FAILED {
+                return (Outcome::Failed, gameboy.cycles());
+            }
+        }
+        gameboy.step_instruction();
+
+        let bytes = output.take();
+        if !bytes.is_empty() {
+            serial.extend(bytes.iter().map(|&b| b as char));
+            if serial.contains("Passed") {
+                return (Outcome::Passed, gameboy.cycles());
+            }
+            if serial.contains("Failed") {
+                return (Outcome::Failed, gameboy.cycles());
+            }
+        }
+    }
+    (Outcome::Timeout, gameboy.cycles())
+}
+
+#[test]
+fn test_roms() {
+    let dir = PathBuf::from(env::var("TEST_ROMS").unwrap_or_else(|_| "test-roms".to_string()));
+    let budget = env::var("TEST_ROMS_CYCLES")
+        .ok()
+        .and_then(|cycles| cycles.parse().ok())
+        .unwrap_or(60 * CLOCK_SPEED);
+    let mut roms = vec![];
+    find_roms(&dir, &mut roms);
+    if roms.is_empty() {
+        println!("No test ROMs found in {}, skipping", dir.display());
+        return;
+    }
+
+    let mut passed = 0;
+    println!("{:<60} {:>12}  RESULT", "ROM", "SECONDS");
+    for rom in &roms {
+        let (outcome, cycles) = run(rom, budget);
+        if outcome == Outcome::Passed {
+            passed += 1;
+        }
+        let name = rom.strip_prefix(&dir).unwrap_or(rom);
+        println!(
+            "{:<60} {:>12.2}  {}",
+            name.display(),
+            cycles as f64 / CLOCK_SPEED as f64,
+            outcome
+        );
+    }
+    println!("{}/{} passed", passed, roms.len());
+}
@@ -1,25 +1,77 @@
 import * as wasm from "gameboy";
 
-const emu = wasm.get_emulator("hackfest.gb");
+const ROM = "hackfest.gb";
+const SAVE_KEY = `save:${ROM}`;
+const AUTOSAVE_INTERVAL_MS = 5000;
+
+const emu = wasm.get_emulator(ROM);
 const ctx = display.getContext('2d');
 
 let frame_start_time = null;
 let req = null;
 
-const rom = fetch('hackfest.gb').then(rom => {
+const loadSave = () => {
+  const saved = window.localStorage.getItem(SAVE_KEY);
+  if(saved !== null) {
+    const data = Uint8Array.from(atob(saved), c => c.charCodeAt(0));
+    wasm.import_save_data(emu, data);
+    console.log("Save loaded");
+  }
+};
+
+const storeSave = () => {
+  if(!wasm.has_battery(emu)) {
+    return;
+  }
+  const data = wasm.export_save_data(emu);
+  window.localStorage.setItem(SAVE_KEY, btoa(Array.from(data, b => String.fromCharCode(b)).join('')));
+};
+
+const rom = fetch(ROM).then(rom => {
   rom.blob().then(blob => {
     var reader = new FileReader();
     reader.addEventListener("loadend", function() {
       const data = new Uint8Array(reader.result);
       wasm.load_cartridge_data(emu, data);
-      wasm.reset(emu);
       console.log("ROM loaded");
+      if(wasm.has_battery(emu)) {
+        loadSave();
+        window.setInterval(storeSave, AUTOSAVE_INTERVAL_MS);
+        window.addEventListener('beforeunload', storeSave);
+      }
       req = window.requestAnimationFrame(step_emulator);
     });
     reader.readAsArrayBuffer(blob);
   });
 });
 
+const KEYS = {
+  ArrowRight: wasm.Button.Right,
+  ArrowLeft: wasm.Button.Left,
+  ArrowUp: wasm.Button.Up,
+  ArrowDown: wasm.Button.Down,
+  x: wasm.Button.A,
+  z: wasm.Button.B,
+  Backspace: wasm.Button.Select,
+  Enter: wasm.Button.Start,
+};
+
+const handleKey = (e, press) => {
+  const button = KEYS[e.key];
+  if(button === undefined) {
+    return;
+  }
+  e.preventDefault();
+  if(press) {
+    wasm.press_button(emu, button);
+  } else {
+    wasm.release_button(emu, button);
+  }
+};
+
+document.addEventListener('keydown', e => handleKey(e, true));
+document.addEventListener('keyup', e => handleKey(e, false));
+
 let prev_ts = 0;
 const step_emulator = ts => {
   const draw = wasm.run_until_redraw(emu);